@@ -0,0 +1,14 @@
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn can_add() {
+        // 📖 #START <id:add> <lang:rust>
+        let sum = 1 + 2;
+        assert_eq!(sum, 3);
+        // 📖 #END
+    }
+}
+
+// 📖 #START <id:text>
+// not a rust block
+// 📖 #END
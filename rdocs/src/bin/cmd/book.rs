@@ -0,0 +1,56 @@
+use std::path::{Path, PathBuf};
+
+use rdocs::{book, cli::CmdExit, collect, command::CacheMode, out, parser};
+
+pub fn exec(
+    config_path: Option<&PathBuf>,
+    collect_folder: &Path,
+    output: &Path,
+    layout_path: Option<&PathBuf>,
+    style: book::Style,
+) -> CmdExit {
+    let span = tracing::span!(tracing::Level::TRACE, "exec");
+    let _guard = span.enter();
+
+    let config = match super::load_config(config_path) {
+        Ok(config) => config,
+        Err(err) => return err,
+    };
+    let layout = match load_layout(layout_path) {
+        Ok(layout) => layout,
+        Err(err) => return err,
+    };
+    let collector = match collect::Collector::from_config(collect_folder, &config.collector) {
+        Ok(collector) => collector,
+        Err(err) => {
+            return CmdExit::error_with_message(&format!("could not init collector: {err}"));
+        }
+    };
+
+    let parser = parser::Parser::with_config(config.parser).with_command_cache(CacheMode::Use);
+    let parser_result = parser.extract_content(&collector);
+
+    if parser_result.is_empty() {
+        return CmdExit::error_with_message("code captures not found in the given path");
+    }
+
+    let content = out::Content::Book(out::Output::Path(output.to_path_buf()), layout, style);
+    if let Err(err) = content.export(parser_result) {
+        return CmdExit::error_with_message(&format!("could not generate book: {err}"));
+    }
+
+    CmdExit::ok_with_message(&format!("Generated book in {}", output.display()))
+}
+
+fn load_layout(layout_path: Option<&PathBuf>) -> Result<book::Layout, CmdExit> {
+    let Some(path) = layout_path else {
+        return Ok(book::Layout::default());
+    };
+
+    let rdr = std::fs::File::open(path).map_err(|err| {
+        CmdExit::error_with_message(&format!("could not read book layout: {err}"))
+    })?;
+
+    serde_yaml::from_reader(rdr)
+        .map_err(|err| CmdExit::error_with_message(&format!("invalid book layout: {err}")))
+}
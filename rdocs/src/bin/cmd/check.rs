@@ -0,0 +1,37 @@
+use std::path::{Path, PathBuf};
+
+use rdocs::{cli::CmdExit, results};
+
+pub fn exec(
+    config_path: Option<&PathBuf>,
+    collect_folder: &Path,
+    replace_folder: &Path,
+    format: Option<results::Format>,
+) -> CmdExit {
+    let span = tracing::span!(tracing::Level::TRACE, "exec");
+    let _guard = span.enter();
+
+    // commands always run, to verify that the recorded outputs are current
+    let report = match super::replace::run(
+        config_path,
+        collect_folder,
+        replace_folder,
+        true,
+        super::ExtractOptions::default(),
+    ) {
+        Ok(report) => report,
+        Err(err) => return err,
+    };
+
+    if let Err(err) = super::replace::print_results(&report.results, format.as_ref(), true) {
+        return err;
+    }
+
+    let outdated = report.outdated().count();
+
+    if outdated > 0 {
+        CmdExit::error_with_message(&format!("Found {outdated} outdated documentation blocks"))
+    } else {
+        CmdExit::ok()
+    }
+}
@@ -1,65 +1,52 @@
 use std::path::{Path, PathBuf};
 
-use rdocs::{cli::CmdExit, collect, out, parser};
-use serde::{Deserialize, Serialize};
-
-#[derive(Default, Clone, Debug, Serialize, Deserialize)]
-pub struct Config {
-    parser: parser::Config,
-    collector: collect::Config,
-}
+use rdocs::{cli::CmdExit, out};
 
 pub fn exec(
     config_path: Option<&PathBuf>,
     collect_folder: &Path,
     format: Option<out::Format>,
     output: Option<PathBuf>,
+    prune: bool,
+    extract: super::ExtractOptions,
 ) -> CmdExit {
     let span = tracing::span!(tracing::Level::TRACE, "exec");
     let _guard = span.enter();
 
-    let config = match config_path {
-        Some(path) => {
-            let rdr = match std::fs::File::open(path) {
-                Ok(rdr) => rdr,
-                Err(err) => {
-                    return CmdExit::error_with_message(&format!(
-                        "could not read config file: {err}"
-                    ));
-                }
-            };
-
-            match serde_yaml::from_reader(rdr) {
-                Ok(config) => config,
-                Err(err) => {
-                    return CmdExit::error_with_message(&format!("invalid config file: {err}"));
-                }
-            }
-        }
-        None => Config::default(),
+    let config = match super::load_config(config_path) {
+        Ok(config) => config,
+        Err(err) => return err,
     };
 
-    let collector = match collect::Collector::from_config(collect_folder, &config.collector) {
-        Ok(collector) => collector,
-        Err(err) => {
-            return CmdExit::error_with_message(&format!("could not init collector: {err}"));
-        }
+    let results = match rdocs::run_collect(&config, collect_folder, extract) {
+        Ok(report) => report.blocks,
+        Err(err) => return super::run_error(&err),
     };
-    let parser = parser::Parser::with_config(config.parser);
-
-    let results = parser.extract_content(&collector);
 
     if results.is_empty() {
         CmdExit::error_with_message("code captures not found in the given path")
     } else {
+        let mut layout = config.export.clone();
+        layout.prune |= prune;
         let out = match format {
             Some(format) => out::Content::All(out::Output::new(output), format),
-            None => out::Content::Only(out::Output::new(output)),
+            None => out::Content::Only(out::Output::new(output), layout.clone()),
         };
-        if let Err(err) = out.export(results) {
-            CmdExit::error_with_message(&format!("export result error: {err}"))
-        } else {
-            CmdExit::ok()
+        match out.export(results) {
+            Err(err) => CmdExit::error_with_message(&format!("export result error: {err}")),
+            Ok(stale) if stale.is_empty() => CmdExit::ok(),
+            Ok(stale) if layout.prune => {
+                CmdExit::ok_with_message(&format!("Removed {} stale files", stale.len()))
+            }
+            Ok(stale) => CmdExit::ok_with_message(&format!(
+                "Found {} stale files, run with --prune to remove them:\n{}",
+                stale.len(),
+                stale
+                    .iter()
+                    .map(|file| format!("  {} ({})", file.path, file.id))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            )),
         }
     }
 }
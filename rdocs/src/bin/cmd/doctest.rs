@@ -0,0 +1,80 @@
+use std::path::{Path, PathBuf};
+
+use rdocs::{
+    cli::CmdExit,
+    collect,
+    command::CacheMode,
+    doctest, parser,
+    verify::{Lang, Verifier},
+};
+
+pub fn exec(
+    config_path: Option<&PathBuf>,
+    collect_folder: &Path,
+    output: Option<PathBuf>,
+    style: doctest::Style,
+    check: bool,
+) -> CmdExit {
+    let span = tracing::span!(tracing::Level::TRACE, "exec");
+    let _guard = span.enter();
+
+    let config = match super::load_config(config_path) {
+        Ok(config) => config,
+        Err(err) => return err,
+    };
+    let collector = match collect::Collector::from_config(collect_folder, &config.collector) {
+        Ok(collector) => collector,
+        Err(err) => {
+            return CmdExit::error_with_message(&format!("could not init collector: {err}"));
+        }
+    };
+
+    let parser = parser::Parser::with_config(config.parser).with_command_cache(CacheMode::Use);
+    let parser_result = parser.extract_content(&collector);
+
+    let verifier = Verifier::new(config.verify);
+    let contents = parser_result
+        .iter()
+        .filter(|content| verifier.lang(content) == Some(Lang::Rust))
+        .collect::<Vec<_>>();
+
+    if contents.is_empty() {
+        return CmdExit::error_with_message("rust blocks not found in the given path");
+    }
+
+    let rendered = doctest::render(&contents, &collector.folder, style);
+
+    let Some(output) = output else {
+        print!("{rendered}");
+        return CmdExit::ok();
+    };
+
+    if check {
+        return match std::fs::read_to_string(&output) {
+            Ok(current) if current == rendered => CmdExit::ok(),
+            Ok(_) => CmdExit::error_with_message(&format!(
+                "{} is outdated, run the doctest command to update it",
+                output.display()
+            )),
+            Err(err) => {
+                CmdExit::error_with_message(&format!("could not read {}: {err}", output.display()))
+            }
+        };
+    }
+
+    if let Some(parent) = output.parent() {
+        if let Err(err) = std::fs::create_dir_all(parent) {
+            return CmdExit::error_with_message(&format!("could not create folder: {err}"));
+        }
+    }
+    match std::fs::write(&output, rendered) {
+        Ok(()) => CmdExit::ok_with_message(&format!(
+            "Generated {} tests in {}",
+            contents.len(),
+            output.display()
+        )),
+        Err(err) => {
+            CmdExit::error_with_message(&format!("could not write {}: {err}", output.display()))
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, BufRead, IsTerminal, Write},
+    path::{Path, PathBuf},
+};
+
+use rdocs::{
+    cli::CmdExit,
+    diff,
+    replacer::{self, ReplaceResult, ReplaceStatus},
+};
+
+/// Represents the user answer for a pending replacement.
+enum Answer {
+    Yes,
+    No,
+    All,
+    Quit,
+}
+
+pub fn exec(
+    config_path: Option<&PathBuf>,
+    collect_folder: &Path,
+    replace_folder: &Path,
+    context: usize,
+    extract: super::ExtractOptions,
+) -> CmdExit {
+    let span = tracing::span!(tracing::Level::TRACE, "exec");
+    let _guard = span.enter();
+
+    let (parser_result, collector) =
+        match super::replace::prepare(config_path, collect_folder, replace_folder, extract) {
+            Ok(res) => res,
+            Err(err) => return err,
+        };
+
+    let replace = replacer::Replace::default();
+
+    let mut files: BTreeMap<PathBuf, Vec<ReplaceResult>> = BTreeMap::new();
+    for result in replace.stats(&collector, &parser_result) {
+        if matches!(result.status, ReplaceStatus::Replaced(_, _, _, _)) {
+            files.entry(result.path.clone()).or_default().push(result);
+        }
+    }
+
+    if files.is_empty() {
+        return CmdExit::ok_with_message("All documentation blocks are up to date");
+    }
+
+    let colorize = io::stdout().is_terminal() && std::env::var("NO_COLOR").is_err();
+    let mut input = io::stdin().lock();
+    let mut replaced_count = 0;
+    let mut quit = false;
+
+    for (path, pending) in &files {
+        let mut accepted = vec![];
+        let mut accept_all = false;
+
+        for result in pending {
+            let ReplaceStatus::Replaced(id, _, block, previous) = &result.status else {
+                continue;
+            };
+
+            if !accept_all {
+                let block_diff = diff::block(previous, block, context);
+                println!(
+                    "{}:{} <id:{id}>",
+                    path.display(),
+                    result.line.unwrap_or_default()
+                );
+                if colorize {
+                    print!("{}", diff::colorize(&block_diff));
+                } else {
+                    print!("{block_diff}");
+                }
+
+                match prompt(&mut input) {
+                    Ok(Answer::Yes) => {}
+                    Ok(Answer::No) => continue,
+                    Ok(Answer::All) => accept_all = true,
+                    Ok(Answer::Quit) => {
+                        quit = true;
+                        break;
+                    }
+                    Err(err) => {
+                        return CmdExit::error_with_message(&format!(
+                            "could not read the answer: {err}"
+                        ));
+                    }
+                }
+            }
+            accepted.push(id.as_str());
+        }
+
+        if !accepted.is_empty() {
+            let contents = parser_result
+                .iter()
+                .filter(|content| accepted.contains(&content.metadata.id.as_str()))
+                .cloned()
+                .collect::<Vec<_>>();
+
+            match replace.replace_with_save(path, &contents) {
+                Ok(results) => {
+                    rdocs::run::record_sync_state(&collector.folder, &contents, &results);
+                    replaced_count += results
+                        .iter()
+                        .filter(|r| matches!(r.status, ReplaceStatus::Replaced(_, _, _, _)))
+                        .count();
+                }
+                Err(err) => {
+                    return CmdExit::error_with_message(&format!(
+                        "could not save {}: {err}",
+                        path.display()
+                    ));
+                }
+            }
+        }
+
+        if quit {
+            break;
+        }
+    }
+
+    CmdExit::ok_with_message(&format!("Replaced {replaced_count} documentation blocks"))
+}
+
+/// Ask the user what to do with the pending replacement until a valid answer
+/// is given. End of input is treated as quit.
+fn prompt(input: &mut impl BufRead) -> io::Result<Answer> {
+    loop {
+        print!("Apply this change? [y]es, [n]o, [a]ll in this file, [q]uit: ");
+        io::stdout().flush()?;
+
+        let mut answer = String::new();
+        if input.read_line(&mut answer)? == 0 {
+            println!();
+            return Ok(Answer::Quit);
+        }
+
+        match answer.trim().to_lowercase().as_str() {
+            "y" | "yes" => return Ok(Answer::Yes),
+            "n" | "no" => return Ok(Answer::No),
+            "a" | "all" => return Ok(Answer::All),
+            "q" | "quit" => return Ok(Answer::Quit),
+            _ => println!("invalid answer"),
+        }
+    }
+}
@@ -1,2 +1,44 @@
+pub mod book;
+pub mod check;
 pub mod collect;
+pub mod doctest;
+pub mod interactive;
+pub mod pull;
 pub mod replace;
+pub mod report;
+
+use std::path::PathBuf;
+
+pub use rdocs::run::ExtractOptions;
+use rdocs::{cli::CmdExit, config::Config, errors::RunError};
+
+/// Convert the given run error to a command exit, printing the blocks that
+/// failed the verification.
+pub fn run_error(err: &RunError) -> CmdExit {
+    let RunError::VerifyFailed { failures } = err else {
+        return CmdExit::error_with_message(&err.to_string());
+    };
+
+    for failure in failures {
+        eprintln!(
+            "{}:{} <id:{}> {} verification failed:\n{}\n",
+            failure.path.display(),
+            failure.line,
+            failure.id,
+            failure.lang,
+            failure.message
+        );
+    }
+
+    CmdExit::error_with_message(&format!(
+        "Found {} blocks that failed verification",
+        failures.len()
+    ))
+}
+
+/// Load the command configuration from the given path or fallback to the
+/// default configuration.
+pub fn load_config(config_path: Option<&PathBuf>) -> Result<Config, CmdExit> {
+    Config::load(config_path.map(PathBuf::as_path))
+        .map_err(|err| CmdExit::error_with_message(&err.to_string()))
+}
@@ -0,0 +1,95 @@
+use std::path::{Path, PathBuf};
+
+use rdocs::{
+    cli::CmdExit,
+    collect,
+    command::CacheMode,
+    parser, replacer,
+    sync::{self, PullStatus},
+};
+use tabled::{builder::Builder, settings::Style};
+
+pub fn exec(
+    config_path: Option<&PathBuf>,
+    collect_folder: &Path,
+    replace_folder: &Path,
+    dry_run: bool,
+) -> CmdExit {
+    let span = tracing::span!(tracing::Level::TRACE, "exec");
+    let _guard = span.enter();
+
+    let config = match super::load_config(config_path) {
+        Ok(config) => config,
+        Err(err) => return err,
+    };
+    let collector = match collect::Collector::from_config(collect_folder, &config.collector) {
+        Ok(collector) => collector,
+        Err(err) => {
+            return CmdExit::error_with_message(&format!("could not init collector: {err}"));
+        }
+    };
+
+    let patterns = config.parser.all_patterns();
+    let parser = parser::Parser::with_config(config.parser).with_command_cache(CacheMode::Use);
+    let parser_result = parser.extract_content(&collector);
+
+    let collector = match collect::Collector::from_config(replace_folder, &config.collector) {
+        Ok(collector) => collector,
+        Err(err) => {
+            return CmdExit::error_with_message(&format!("could not init collector: {err}"));
+        }
+    };
+
+    let mut state = match sync::SyncState::load(&collector.folder) {
+        Ok(state) => state,
+        Err(err) => {
+            return CmdExit::error_with_message(&format!("could not load sync state: {err}"));
+        }
+    };
+
+    let replace = replacer::Replace::default();
+    let targets = sync::target_blocks(&replace, replace.markers(&collector));
+    let pull_results = sync::pull(&parser_result, &targets, &patterns, &mut state, dry_run);
+
+    if !dry_run {
+        if let Err(err) = state.save(&collector.folder) {
+            return CmdExit::error_with_message(&format!("could not save sync state: {err}"));
+        }
+    }
+
+    let mut builder = Builder::default();
+    builder.push_record(["id", "status", "path"]);
+
+    for result in &pull_results {
+        let (id, status) = match &result.status {
+            PullStatus::Equal(_) => continue,
+            PullStatus::Pulled(id, _) => (id.as_str(), "pulled".to_string()),
+            PullStatus::Conflict(id) => (id.as_str(), "conflict".to_string()),
+            PullStatus::Unknown(id) => (id.as_str(), "unknown sync state".to_string()),
+            PullStatus::Error(id, err) => (id.as_str(), format!("error: {err}")),
+        };
+        builder.push_record([id.to_string(), status, result.path.display().to_string()]);
+    }
+
+    if builder.count_records() == 1 {
+        return CmdExit::ok_with_message("Not found edited blocks to pull");
+    }
+
+    if std::env::var("TEST").is_ok() {
+        let res: Vec<Vec<String>> = builder.into();
+        println!("{res:#?}");
+    } else {
+        let table = builder.build().with(Style::modern()).to_string();
+        println!("{table}");
+    }
+
+    let has_error = pull_results
+        .iter()
+        .any(|r| matches!(r.status, PullStatus::Error(_, _)));
+
+    if has_error {
+        CmdExit::error_with_message("Finished with errors")
+    } else {
+        CmdExit::ok()
+    }
+}
@@ -1,17 +1,25 @@
-use std::path::{Path, PathBuf};
+use std::{
+    io::IsTerminal,
+    path::{Path, PathBuf},
+};
 
 use rdocs::{
     cli::CmdExit,
-    collect, parser,
-    replacer::{self, ReplaceStatus},
+    collect,
+    command::CacheMode,
+    diff, parser,
+    replacer::{ReplaceResult, ReplaceStatus},
+    results,
+    run::ReplaceReport,
 };
-use serde::{Deserialize, Serialize};
 use tabled::{builder::Builder, settings::Style};
 
-#[derive(Default, Clone, Debug, Serialize, Deserialize)]
-pub struct Config {
-    parser: parser::Config,
-    collector: collect::Config,
+/// Dry run diff options.
+pub struct DiffOptions {
+    /// Number of context lines.
+    pub context: usize,
+    /// Save the diff to the given patch file.
+    pub patch: Option<PathBuf>,
 }
 
 pub fn exec(
@@ -19,65 +27,139 @@ pub fn exec(
     collect_folder: &Path,
     replace_folder: &Path,
     dry_run: bool,
+    format: Option<results::Format>,
+    diff_options: &DiffOptions,
+    extract: super::ExtractOptions,
 ) -> CmdExit {
     let span = tracing::span!(tracing::Level::TRACE, "exec");
     let _guard = span.enter();
 
-    let config = match config_path {
-        Some(path) => {
-            let rdr = match std::fs::File::open(path) {
-                Ok(rdr) => rdr,
-                Err(err) => {
-                    return CmdExit::error_with_message(&format!(
-                        "could not read config file: {err}"
-                    ));
-                }
-            };
-
-            match serde_yaml::from_reader(rdr) {
-                Ok(config) => config,
-                Err(err) => {
-                    return CmdExit::error_with_message(&format!("invalid config file: {err}"));
-                }
-            }
-        }
-        None => Config::default(),
+    let mut extract = extract;
+    if dry_run && extract.command_cache == CacheMode::Record {
+        extract.command_cache = CacheMode::Off;
+    }
+
+    let report = match run(
+        config_path,
+        collect_folder,
+        replace_folder,
+        dry_run,
+        extract,
+    ) {
+        Ok(report) => report,
+        Err(err) => return err,
     };
-    let collector = match collect::Collector::from_config(collect_folder, &config.collector) {
-        Ok(collector) => collector,
-        Err(err) => {
-            return CmdExit::error_with_message(&format!("could not init collector: {err}"));
+    let replace_results = &report.results;
+
+    if let Err(err) = print_results(replace_results, format.as_ref(), false) {
+        return err;
+    }
+
+    if dry_run {
+        if let Err(err) = print_diff(replace_results, format.is_none(), diff_options) {
+            return err;
         }
-    };
+    }
 
-    let parser = parser::Parser::with_config(config.parser);
-    let parser_result = parser.extract_content(&collector);
+    if report.errors().next().is_some() {
+        CmdExit::error_with_message("Finished with errors")
+    } else {
+        CmdExit::ok()
+    }
+}
 
-    let collector = match collect::Collector::from_config(replace_folder, &config.collector) {
-        Ok(collector) => collector,
-        Err(err) => {
-            return CmdExit::error_with_message(&format!("could not init collector: {err}"));
+/// Collect the documentation blocks, replace them in the replace folder and
+/// render the templates of the replace folder. When `dry_run` is `true` the
+/// replace results are calculated without changing the files.
+pub fn run(
+    config_path: Option<&PathBuf>,
+    collect_folder: &Path,
+    replace_folder: &Path,
+    dry_run: bool,
+    extract: super::ExtractOptions,
+) -> Result<ReplaceReport, CmdExit> {
+    let config = super::load_config(config_path)?;
+    rdocs::run_replace(&config, collect_folder, replace_folder, dry_run, extract)
+        .map_err(|err| super::run_error(&err))
+}
+
+/// Collect the documentation blocks and create the collector of the replace
+/// folder.
+pub fn prepare(
+    config_path: Option<&PathBuf>,
+    collect_folder: &Path,
+    replace_folder: &Path,
+    extract: super::ExtractOptions,
+) -> Result<(Vec<parser::ContentResults>, collect::Collector), CmdExit> {
+    let config = super::load_config(config_path)?;
+    let (report, collector) = rdocs::run::prepare(&config, collect_folder, replace_folder, extract)
+        .map_err(|err| super::run_error(&err))?;
+    if let Some(failure) = report.command_failures.first() {
+        return Err(CmdExit::error_with_message(&format!(
+            "could not run command: {}",
+            failure.error
+        )));
+    }
+    Ok((report.blocks, collector))
+}
+
+/// Print the dry run changes as a unified diff and save them to a patch file
+/// when requested.
+fn print_diff(
+    replace_results: &[ReplaceResult],
+    show: bool,
+    diff_options: &DiffOptions,
+) -> Result<(), CmdExit> {
+    let diffs = diff::FileDiff::from_results(replace_results)
+        .map_err(|err| CmdExit::error_with_message(&format!("could not create diff: {err}")))?;
+    let base = std::env::current_dir().ok();
+
+    if show {
+        for file_diff in &diffs {
+            let unified = file_diff.unified(base.as_deref(), diff_options.context);
+            if std::io::stdout().is_terminal() && std::env::var("NO_COLOR").is_err() {
+                print!("{}", diff::colorize(&unified));
+            } else {
+                print!("{unified}");
+            }
         }
-    };
+    }
 
-    let replace_results = {
-        let mut replace_results = if dry_run {
-            replacer::Replace::default().stats(&collector, &parser_result)
-        } else {
-            replacer::Replace::default().replace_content(&collector, &parser_result)
-        };
-        replace_results.sort_by(|a, b| a.path.file_name().cmp(&b.path.file_name()));
-        replace_results
-    };
+    if let Some(patch_path) = &diff_options.patch {
+        let patch = diff::patch(&diffs, base.as_deref(), diff_options.context);
+        std::fs::write(patch_path, patch).map_err(|err| {
+            CmdExit::error_with_message(&format!("could not save patch file: {err}"))
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Print the replace results as a table or in the given format.
+pub fn print_results(
+    replace_results: &[ReplaceResult],
+    format: Option<&results::Format>,
+    check: bool,
+) -> Result<(), CmdExit> {
+    if let Some(format) = format {
+        let base = std::env::current_dir().ok();
+        let content = format
+            .render(replace_results, check, base.as_deref())
+            .map_err(|err| {
+                CmdExit::error_with_message(&format!("could not render results: {err}"))
+            })?;
+        println!("{content}");
+        return Ok(());
+    }
 
     let mut builder = Builder::default();
     builder.push_record(["id", "status", "path"]);
 
-    for result in &replace_results {
+    for result in replace_results {
         let (id, content) = match &result.status {
             ReplaceStatus::NotFound(_) | ReplaceStatus::Error(_) => continue,
             ReplaceStatus::Equal(id) => (id.to_string(), String::new()),
-            ReplaceStatus::Replaced(id, _, block) => (id.to_string(), block.to_string()),
+            ReplaceStatus::Replaced(id, _, block, _) => (id.to_string(), block.to_string()),
         };
 
         builder.push_record([
@@ -96,18 +178,8 @@ pub fn exec(
             let table = builder.build().with(Style::modern()).to_string();
             println!("{table}");
         }
+        Ok(())
     } else {
-        return CmdExit::error_with_message("Not found block to replace");
-    }
-
-    let has_error = replace_results
-        .iter()
-        .filter(|&r| matches!(r.status, ReplaceStatus::Error(_)))
-        .count();
-
-    if has_error > 0 {
-        CmdExit::error_with_message("Finished with errors")
-    } else {
-        CmdExit::ok()
+        Err(CmdExit::error_with_message("Not found block to replace"))
     }
 }
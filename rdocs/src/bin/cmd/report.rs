@@ -0,0 +1,86 @@
+use std::path::{Path, PathBuf};
+
+use rdocs::{cli::CmdExit, collect, command::CacheMode, parser, replacer, report::Report};
+use tabled::{builder::Builder, settings::Style};
+
+pub fn exec(
+    config_path: Option<&PathBuf>,
+    collect_folder: &Path,
+    replace_folder: &Path,
+    strict: bool,
+) -> CmdExit {
+    let span = tracing::span!(tracing::Level::TRACE, "exec");
+    let _guard = span.enter();
+
+    let config = match super::load_config(config_path) {
+        Ok(config) => config,
+        Err(err) => return err,
+    };
+    let collector = match collect::Collector::from_config(collect_folder, &config.collector) {
+        Ok(collector) => collector,
+        Err(err) => {
+            return CmdExit::error_with_message(&format!("could not init collector: {err}"));
+        }
+    };
+
+    let parser = parser::Parser::with_config(config.parser).with_command_cache(CacheMode::Use);
+    let parser_result = parser.extract_content(&collector);
+
+    let collector = match collect::Collector::from_config(replace_folder, &config.collector) {
+        Ok(collector) => collector,
+        Err(err) => {
+            return CmdExit::error_with_message(&format!("could not init collector: {err}"));
+        }
+    };
+    let markers = replacer::Replace::default().markers(&collector);
+
+    let report = Report::new(&parser_result, &markers);
+
+    let mut builder = Builder::default();
+    builder.push_record(["id", "status", "path", "usages"]);
+
+    for usage in &report.usages {
+        builder.push_record([
+            usage.id.clone(),
+            "used".to_string(),
+            usage.path.display().to_string(),
+            usage.count.to_string(),
+        ]);
+    }
+
+    for source in &report.unused_sources {
+        builder.push_record([
+            source.id.clone(),
+            "unused".to_string(),
+            format!("{}:{}", source.path.display(), source.line),
+            "0".to_string(),
+        ]);
+    }
+
+    for marker in &report.dangling_targets {
+        builder.push_record([
+            marker.id.clone(),
+            "dangling".to_string(),
+            format!("{}:{}", marker.path.display(), marker.line),
+            String::new(),
+        ]);
+    }
+
+    if builder.count_records() == 1 {
+        return CmdExit::error_with_message("Not found blocks or markers to report");
+    }
+
+    if std::env::var("TEST").is_ok() {
+        let res: Vec<Vec<String>> = builder.into();
+        println!("{res:#?}");
+    } else {
+        let table = builder.build().with(Style::modern()).to_string();
+        println!("{table}");
+    }
+
+    if strict && report.has_issues() {
+        CmdExit::error_with_message("Found unused sources or dangling targets")
+    } else {
+        CmdExit::ok()
+    }
+}
@@ -1,7 +1,7 @@
 use std::path::PathBuf;
 mod cmd;
 use clap::{ArgAction, Parser, Subcommand};
-use rdocs::out;
+use rdocs::{book, doctest, out, results};
 use tracing::level_filters::LevelFilter;
 use tracing_subscriber::EnvFilter;
 
@@ -37,6 +37,19 @@ enum Commands {
         /// Result output
         #[arg(short, long, value_enum, default_value = None)]
         format: Option<out::Format>,
+
+        /// Use the recorded command outputs instead of running the commands
+        #[clap(long, action=ArgAction::SetTrue)]
+        cached_commands: bool,
+
+        /// Verify that the blocks compile before collecting them
+        #[clap(long, action=ArgAction::SetTrue)]
+        verify: bool,
+
+        /// Remove the files of previous exports to the output folder that were
+        /// not exported again
+        #[clap(long, action=ArgAction::SetTrue, requires = "output", conflicts_with = "format")]
+        prune: bool,
     },
     /// Collect documentation blocks and replace with a given target
     Replace {
@@ -47,6 +60,92 @@ enum Commands {
         /// Show the replacement operation without changes
         #[clap(long, action=ArgAction::SetTrue)]
         dry_run: bool,
+
+        /// Review each pending replacement before saving it
+        #[clap(short, long, action=ArgAction::SetTrue, conflicts_with_all = ["dry_run", "format"])]
+        interactive: bool,
+
+        /// Replace results output
+        #[arg(short, long, value_enum, default_value = None)]
+        format: Option<results::Format>,
+
+        /// Number of context lines in the dry run diff
+        #[arg(long, default_value = "3")]
+        context: usize,
+
+        /// Save the dry run changes to a patch file that can be applied with
+        /// `git apply`
+        #[arg(long, requires = "dry_run", default_value = None)]
+        patch: Option<PathBuf>,
+
+        /// Use the recorded command outputs instead of running the commands
+        #[clap(long, action=ArgAction::SetTrue)]
+        cached_commands: bool,
+
+        /// Verify that the blocks compile before replacing them
+        #[clap(long, action=ArgAction::SetTrue)]
+        verify: bool,
+    },
+    /// Check that the documentation blocks in the given target are up to date
+    Check {
+        /// Location of replacement content. if empty take the default path
+        #[clap(index = 2)]
+        replace_path: Option<PathBuf>,
+
+        /// Check results output
+        #[arg(short, long, value_enum, default_value = None)]
+        format: Option<results::Format>,
+    },
+    /// Write documentation blocks that were edited in the target back into the
+    /// source code
+    Pull {
+        /// Location of replacement content. if empty take the default path
+        #[clap(index = 2)]
+        replace_path: Option<PathBuf>,
+
+        /// Show the pull operation without changes
+        #[clap(long, action=ArgAction::SetTrue)]
+        dry_run: bool,
+    },
+    /// Generate tests from the extracted Rust blocks
+    Doctest {
+        /// Save the tests to the given file. if not provided the tests will
+        /// print to the stdout
+        #[arg(short, long, default_value = None)]
+        output: Option<PathBuf>,
+
+        /// Generated tests style
+        #[arg(short, long, value_enum, default_value = "test")]
+        style: doctest::Style,
+
+        /// Check that the given output file is up to date without changing it
+        #[clap(long, action=ArgAction::SetTrue, requires = "output")]
+        check: bool,
+    },
+    /// Generate an mdBook source tree or static HTML pages from the
+    /// documentation blocks
+    Book {
+        /// Save the pages to the given folder
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Book layout file that groups the blocks into chapters
+        #[arg(long, default_value = None)]
+        layout: Option<PathBuf>,
+
+        /// Generated book style
+        #[arg(short, long, value_enum, default_value = "mdbook")]
+        style: book::Style,
+    },
+    /// Report unused documentation blocks and dangling replacement markers
+    Report {
+        /// Location of replacement content. if empty take the default path
+        #[clap(index = 2)]
+        replace_path: Option<PathBuf>,
+
+        /// Exit with an error when unused blocks or dangling markers found
+        #[clap(long, action=ArgAction::SetTrue)]
+        strict: bool,
     },
 }
 
@@ -68,21 +167,110 @@ fn main() {
 
     // println!("{:#?}", app.command.);
     match app.command {
-        Commands::Collect { output, format } => {
-            cmd::collect::exec(app.config.as_ref(), app.path.as_path(), format, output)
-        }
+        Commands::Collect {
+            output,
+            format,
+            cached_commands,
+            verify,
+            prune,
+        } => cmd::collect::exec(
+            app.config.as_ref(),
+            app.path.as_path(),
+            format,
+            output,
+            prune,
+            cmd::ExtractOptions::new(cached_commands, verify),
+        ),
         Commands::Replace {
             replace_path,
             dry_run,
+            interactive,
+            format,
+            context,
+            patch,
+            cached_commands,
+            verify,
+        } => {
+            let extract = cmd::ExtractOptions::new(cached_commands, verify);
+            let replace_path = replace_path.unwrap_or_else(|| app.path.clone());
+            if interactive {
+                cmd::interactive::exec(
+                    app.config.as_ref(),
+                    app.path.as_path(),
+                    replace_path.as_path(),
+                    context,
+                    extract,
+                )
+            } else {
+                cmd::replace::exec(
+                    app.config.as_ref(),
+                    app.path.as_path(),
+                    replace_path.as_path(),
+                    dry_run,
+                    format,
+                    &cmd::replace::DiffOptions { context, patch },
+                    extract,
+                )
+            }
+        }
+        Commands::Check {
+            replace_path,
+            format,
         } => {
             let replace_path = replace_path.unwrap_or_else(|| app.path.clone());
-            cmd::replace::exec(
+            cmd::check::exec(
+                app.config.as_ref(),
+                app.path.as_path(),
+                replace_path.as_path(),
+                format,
+            )
+        }
+        Commands::Pull {
+            replace_path,
+            dry_run,
+        } => {
+            let replace_path = replace_path.unwrap_or_else(|| app.path.clone());
+            cmd::pull::exec(
                 app.config.as_ref(),
                 app.path.as_path(),
                 replace_path.as_path(),
                 dry_run,
             )
         }
+        Commands::Doctest {
+            output,
+            style,
+            check,
+        } => cmd::doctest::exec(
+            app.config.as_ref(),
+            app.path.as_path(),
+            output,
+            style,
+            check,
+        ),
+        Commands::Book {
+            output,
+            layout,
+            style,
+        } => cmd::book::exec(
+            app.config.as_ref(),
+            app.path.as_path(),
+            output.as_path(),
+            layout.as_ref(),
+            style,
+        ),
+        Commands::Report {
+            replace_path,
+            strict,
+        } => {
+            let replace_path = replace_path.unwrap_or_else(|| app.path.clone());
+            cmd::report::exec(
+                app.config.as_ref(),
+                app.path.as_path(),
+                replace_path.as_path(),
+                strict,
+            )
+        }
     }
     .exit();
 }
@@ -0,0 +1,435 @@
+//! A module for generating a snippet gallery from the extracted blocks.
+//!
+//! The blocks are grouped into chapters by a [`Layout`] file, and rendered as
+//! an mdBook `src/` tree or as static HTML pages.
+//!
+//! ```yaml
+//! title: Snippets
+//! source_url: https://github.com/kaplanelad/rdocs/blob/main/
+//! chapters:
+//!   - title: Getting Started
+//!     ids: [installation, quick-start]
+//!   - title: Rust Examples
+//!     tags: [rust]
+//! ```
+//!
+//! A chapter lists its blocks by ID, in the given order, followed by the
+//! blocks of the given tags sorted by ID. The tags of a block are its
+//! language, its namespace and the comma separated values of its `tag`
+//! attribute.
+use std::{
+    collections::HashSet,
+    fmt::Write,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::{BookError, BookResult},
+    markdown, out,
+    parser::ContentResults,
+};
+
+const HIGHLIGHT_URL: &str = "https://cdnjs.cloudflare.com/ajax/libs/highlight.js/11.9.0";
+
+/// Enum representing the generated book styles.
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[derive(Clone, Copy, Debug, Default)]
+pub enum Style {
+    /// Generate an mdBook `src/` tree with a `SUMMARY.md` file.
+    #[default]
+    Mdbook,
+    /// Generate static HTML pages with an `index.html` file.
+    Html,
+}
+
+/// Represents the book layout.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Layout {
+    /// The book title.
+    #[serde(default = "default_title")]
+    pub title: String,
+    /// The URL prefix of the source links, for example the repository
+    /// `blob/main/` URL. The source locations are displayed without a link
+    /// when not set.
+    #[serde(default)]
+    pub source_url: Option<String>,
+    /// The folder that the source paths are relative to, for example the
+    /// repository root of the `source_url` links. Defaults to the git
+    /// repository root of the current directory, or to the current directory
+    /// outside of a git repository.
+    #[serde(default)]
+    pub source_root: Option<PathBuf>,
+    /// The book chapters. All the blocks are added to a single chapter when
+    /// empty.
+    #[serde(default)]
+    pub chapters: Vec<Chapter>,
+}
+
+/// Represents a book chapter.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Chapter {
+    pub title: String,
+    /// The chapter block IDs, in the chapter order.
+    #[serde(default)]
+    pub ids: Vec<String>,
+    /// The chapter block tags.
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Represents a generated book page.
+#[derive(Debug)]
+pub struct Page {
+    /// The page path, relative to the output folder.
+    pub path: PathBuf,
+    pub content: String,
+}
+
+fn default_title() -> String {
+    "Snippets".to_string()
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            title: default_title(),
+            source_url: None,
+            source_root: None,
+            chapters: vec![],
+        }
+    }
+}
+
+impl Chapter {
+    /// Returns the chapter blocks: the blocks of the chapter IDs in the
+    /// chapter order, followed by the blocks of the chapter tags sorted by
+    /// ID.
+    #[must_use]
+    pub fn blocks<'a>(&self, contents: &'a [ContentResults]) -> Vec<&'a ContentResults> {
+        let mut blocks = vec![];
+        for id in &self.ids {
+            if let Some(content) = contents
+                .iter()
+                .find(|content| content.metadata.id == *id || content.metadata.short_id() == id)
+            {
+                blocks.push(content);
+            } else {
+                tracing::warn!(id, chapter = self.title, "chapter block not found");
+            }
+        }
+
+        let mut tagged = contents
+            .iter()
+            .filter(|content| {
+                !blocks.iter().any(|block| std::ptr::eq(*block, *content))
+                    && tags(content).iter().any(|tag| self.tags.contains(tag))
+            })
+            .collect::<Vec<_>>();
+        tagged.sort_by(|a, b| a.metadata.id.cmp(&b.metadata.id));
+        blocks.extend(tagged);
+        blocks
+    }
+}
+
+/// Returns the tags of the given block.
+fn tags(content: &ContentResults) -> Vec<String> {
+    let mut tags = vec![];
+    tags.extend(content.metadata.lang.clone());
+    tags.extend(content.metadata.namespace.clone());
+    if let Some(tag) = content.metadata.attributes.get("tag") {
+        tags.extend(tag.split(',').map(|tag| tag.trim().to_string()));
+    }
+    tags
+}
+
+impl Layout {
+    /// Render the book pages of the given blocks with the given style.
+    ///
+    /// # Errors
+    /// When the titles of two chapters have the same slug, since their pages
+    /// would overwrite each other
+    pub fn render(&self, contents: &[ContentResults], style: Style) -> BookResult<Vec<Page>> {
+        let base = self.source_root();
+        let chapters = if self.chapters.is_empty() {
+            let mut blocks = contents.iter().collect::<Vec<_>>();
+            blocks.sort_by(|a, b| a.metadata.id.cmp(&b.metadata.id));
+            vec![(self.title.clone(), blocks)]
+        } else {
+            self.chapters
+                .iter()
+                .map(|chapter| (chapter.title.clone(), chapter.blocks(contents)))
+                .collect()
+        };
+
+        let (extension, index_path) = match style {
+            Style::Mdbook => ("md", "SUMMARY.md"),
+            Style::Html => ("html", "index.html"),
+        };
+        let mut paths = HashSet::from([PathBuf::from(index_path)]);
+        let mut pages_chapters = vec![];
+        for (title, blocks) in chapters {
+            let slug = markdown::slug(&title);
+            let path = PathBuf::from(format!("{slug}.{extension}"));
+            if !paths.insert(path.clone()) {
+                return Err(BookError::DuplicateChapter { title, slug });
+            }
+            pages_chapters.push((title, path, blocks));
+        }
+
+        let mut pages = vec![];
+        let index = match style {
+            Style::Mdbook => {
+                let mut summary = format!("# {}\n\n", self.title);
+                for (title, path, blocks) in &pages_chapters {
+                    let _ = writeln!(summary, "- [{title}](./{})", path.display());
+                    let mut content = format!("# {title}\n");
+                    for block in blocks {
+                        content.push_str(&out::markdown_section(block, &self.source(block, &base)));
+                    }
+                    pages.push(Page {
+                        path: path.clone(),
+                        content,
+                    });
+                }
+                summary
+            }
+            Style::Html => {
+                let mut index = format!("<h1>{}</h1>\n<ul>\n", escape_html(&self.title));
+                for (title, path, blocks) in &pages_chapters {
+                    let _ = writeln!(
+                        index,
+                        "<li><a href=\"{}\">{}</a></li>",
+                        path.display(),
+                        escape_html(title)
+                    );
+                    let mut body = format!("<h1>{}</h1>\n", escape_html(title));
+                    for block in blocks {
+                        body.push_str(&self.html_section(block, &base));
+                    }
+                    pages.push(Page {
+                        path: path.clone(),
+                        content: self.html_page(title, &body),
+                    });
+                }
+                index.push_str("</ul>\n");
+                self.html_page(&self.title, &index)
+            }
+        };
+        pages.insert(
+            0,
+            Page {
+                path: PathBuf::from(index_path),
+                content: index,
+            },
+        );
+        Ok(pages)
+    }
+
+    /// Returns the folder that the source paths are relative to.
+    fn source_root(&self) -> PathBuf {
+        let root = self
+            .source_root
+            .clone()
+            .or_else(git_root)
+            .or_else(|| std::env::current_dir().ok())
+            .unwrap_or_default();
+        // the collected paths are canonical
+        root.canonicalize().unwrap_or(root)
+    }
+
+    /// Returns the Markdown source reference of the given block.
+    fn source(&self, content: &ContentResults, base: &Path) -> String {
+        let (path, line) = source_location(content, base);
+        self.source_url.as_ref().map_or_else(
+            || format!("`{path}:{line}`"),
+            |url| format!("[{path}:{line}]({url}{path}#L{line})"),
+        )
+    }
+
+    fn html_section(&self, content: &ContentResults, base: &Path) -> String {
+        let (path, line) = source_location(content, base);
+        let source = self.source_url.as_ref().map_or_else(
+            || format!("<code>{}:{line}</code>", escape_html(&path)),
+            |url| {
+                format!(
+                    "<a href=\"{}{}#L{line}\">{}:{line}</a>",
+                    escape_html(url),
+                    escape_html(&path),
+                    escape_html(&path)
+                )
+            },
+        );
+        let class = content
+            .metadata
+            .lang
+            .as_ref()
+            .map(|lang| format!(" class=\"language-{}\"", escape_html(lang)))
+            .unwrap_or_default();
+
+        format!(
+            "<h2 id=\"{id}\"><code>{id}</code></h2>\n<p>Source: {source}</p>\n<pre><code{class}>{}</code></pre>\n",
+            escape_html(&content.data),
+            id = escape_html(&content.metadata.id),
+        )
+    }
+
+    fn html_page(&self, title: &str, body: &str) -> String {
+        format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{} - {}</title>\n<link rel=\"stylesheet\" href=\"{HIGHLIGHT_URL}/styles/default.min.css\">\n<script src=\"{HIGHLIGHT_URL}/highlight.min.js\"></script>\n<script>hljs.highlightAll();</script>\n</head>\n<body>\n{body}</body>\n</html>\n",
+            escape_html(title),
+            escape_html(&self.title),
+        )
+    }
+}
+
+/// Returns the source path relative to `base` with `/` separators, and the
+/// start line of the given block.
+fn source_location(content: &ContentResults, base: &Path) -> (String, usize) {
+    (
+        content.location.relative_path(base),
+        content.location.start_line,
+    )
+}
+
+/// Returns the git repository root of the current directory.
+fn git_root() -> Option<PathBuf> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--show-toplevel"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        tracing::debug!("git repository not found");
+        return None;
+    }
+    Some(PathBuf::from(
+        String::from_utf8_lossy(&output.stdout).trim(),
+    ))
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+
+    use insta::assert_snapshot;
+
+    use super::*;
+
+    fn content(id: &str, lang: Option<&str>, tag: Option<&str>, data: &str) -> ContentResults {
+        let mut content = ContentResults::new_for_test(id, data, "/repo/src/lib.rs", 3, 5);
+        content.metadata.lang = lang.map(ToString::to_string);
+        if let Some(tag) = tag {
+            content
+                .metadata
+                .attributes
+                .insert("tag".to_string(), tag.to_string());
+        }
+        content
+    }
+
+    fn contents() -> Vec<ContentResults> {
+        vec![
+            content("sum", Some("rust"), None, "let sum = 1 + 2;"),
+            content("install", Some("sh"), Some("setup"), "cargo install rdocs"),
+            content("compare", Some("rust"), None, "assert!(1 < 2 && 2 > 1);"),
+            content("usage", None, Some("setup, cli"), "rdocs collect ."),
+        ]
+    }
+
+    fn layout() -> Layout {
+        serde_yaml::from_str(
+            r"
+title: Gallery
+source_url: https://github.com/kaplanelad/rdocs/blob/main/
+source_root: /repo
+chapters:
+  - title: Getting Started
+    ids: [usage, missing]
+    tags: [setup]
+  - title: Rust Examples
+    tags: [rust]
+",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn can_select_chapter_blocks() {
+        let contents = contents();
+        let layout = layout();
+        let ids = |chapter: &Chapter| {
+            chapter
+                .blocks(&contents)
+                .iter()
+                .map(|block| block.metadata.id.as_str())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(ids(&layout.chapters[0]), vec!["usage", "install"]);
+        assert_eq!(ids(&layout.chapters[1]), vec!["compare", "sum"]);
+    }
+
+    #[test]
+    fn can_render_mdbook() {
+        let pages = layout().render(&contents(), Style::Mdbook).unwrap();
+        let paths = pages
+            .iter()
+            .map(|page| page.path.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("SUMMARY.md"),
+                PathBuf::from("getting-started.md"),
+                PathBuf::from("rust-examples.md"),
+            ]
+        );
+        assert_snapshot!(pages[0].content);
+        assert_snapshot!(pages[1].content);
+    }
+
+    #[test]
+    fn can_render_html() {
+        let layout = Layout {
+            source_root: Some(PathBuf::from("/repo")),
+            ..Layout::default()
+        };
+        let pages = layout.render(&contents(), Style::Html).unwrap();
+        let paths = pages
+            .iter()
+            .map(|page| page.path.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec![PathBuf::from("index.html"), PathBuf::from("snippets.html")]
+        );
+        assert_snapshot!(pages[1].content);
+    }
+
+    #[test]
+    fn render_with_duplicate_chapters() {
+        let layout: Layout = serde_yaml::from_str(
+            r"
+chapters:
+  - title: Getting Started
+    tags: [setup]
+  - title: Getting started!
+    tags: [rust]
+",
+        )
+        .unwrap();
+
+        assert!(matches!(
+            layout.render(&contents(), Style::Mdbook),
+            Err(BookError::DuplicateChapter { slug, .. }) if slug == "getting-started"
+        ));
+    }
+}
@@ -0,0 +1,274 @@
+//! A module for syncing the documentation from a `build.rs` build script.
+//!
+//! The documentation blocks are extracted with the recorded command outputs,
+//! and compared with the target files. Cargo is asked to rerun the build
+//! script when one of the collected folders, source or target files changes,
+//! and the failed or outdated blocks are reported as cargo warnings.
+//!
+//! ```rust,no_run
+//! // in the `main` function of build.rs
+//! let config = rdocs::build::Config::new("src", "docs").with_config_file("rdocs.yaml");
+//! if let Err(err) = rdocs::build::sync_docs(&config) {
+//!     panic!("{err}");
+//! }
+//! ```
+//!
+//! By default the target files are not changed, and the build fails when the
+//! documentation is outdated. With [`Config::with_write`] the target files
+//! are updated instead, which is useful during local development only:
+//! `cargo publish` rejects build scripts that modify the source directory,
+//! and updated target files are reported as changed, so the build script
+//! runs once more in the next build.
+use std::{
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    collect::Collector,
+    command::CacheMode,
+    config,
+    errors::{BuildError, BuildResult, RunError},
+    replacer::ReplaceStatus,
+    run::{self, ExtractOptions, ReplaceReport},
+};
+
+/// Represents the build script sync configuration.
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// The folder to collect the documentation blocks from.
+    pub collect_folder: PathBuf,
+    /// The folder of the target files.
+    pub replace_folder: PathBuf,
+    /// The rdocs configuration file, the default configuration is used when
+    /// not set.
+    pub config_file: Option<PathBuf>,
+    /// Fail the build when the documentation is outdated instead of updating
+    /// the target files.
+    pub check: bool,
+}
+
+impl Config {
+    /// Creates a new [`Config`] that checks the target files of
+    /// `replace_folder` against the blocks of `collect_folder`, without
+    /// changing them. Relative paths are relative to the package folder.
+    #[must_use]
+    pub fn new(collect_folder: impl Into<PathBuf>, replace_folder: impl Into<PathBuf>) -> Self {
+        Self {
+            collect_folder: collect_folder.into(),
+            replace_folder: replace_folder.into(),
+            config_file: None,
+            check: true,
+        }
+    }
+
+    /// Load the rdocs configuration from the given file.
+    #[must_use]
+    pub fn with_config_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config_file = Some(path.into());
+        self
+    }
+
+    /// Update the target files instead of failing the build when the
+    /// documentation is outdated.
+    #[must_use]
+    pub const fn with_write(mut self) -> Self {
+        self.check = false;
+        self
+    }
+}
+
+/// Sync the documentation of the given configuration and print the cargo
+/// build script instructions.
+///
+/// # Errors
+/// When the configuration could not be loaded, the blocks could not be
+/// collected, some blocks failed to be replaced, or when
+/// [`Config::check`] is set and the documentation is outdated
+pub fn sync_docs(config: &Config) -> BuildResult<ReplaceReport> {
+    sync_docs_with_writer(config, &mut io::stdout().lock())
+}
+
+/// Sync the documentation of the given configuration and write the cargo
+/// build script instructions to the given writer.
+///
+/// # Errors
+/// See [`sync_docs`]
+pub fn sync_docs_with_writer(config: &Config, out: &mut impl Write) -> BuildResult<ReplaceReport> {
+    if let Some(path) = &config.config_file {
+        rerun_if_changed(out, path)?;
+    }
+    let rdocs_config = config::Config::load(config.config_file.as_deref())?;
+
+    for folder in [&config.collect_folder, &config.replace_folder] {
+        let collector =
+            Collector::from_config(folder, &rdocs_config.collector).map_err(RunError::Collector)?;
+        rerun_if_changed(out, &collector.folder)?;
+        let mut files = collector.collect_files();
+        files.sort();
+        for path in &files {
+            rerun_if_changed(out, path)?;
+        }
+    }
+
+    let report = run::run_replace(
+        &rdocs_config,
+        &config.collect_folder,
+        &config.replace_folder,
+        config.check,
+        ExtractOptions {
+            // the check does not change the collect folder
+            command_cache: if config.check {
+                CacheMode::Read
+            } else {
+                CacheMode::Use
+            },
+            verify: false,
+        },
+    )?;
+
+    let mut errors = 0;
+    let mut outdated = 0;
+    for result in &report.results {
+        match &result.status {
+            ReplaceStatus::Error(message) => {
+                errors += 1;
+                warning(out, &result.path, result.line, message)?;
+            }
+            ReplaceStatus::Replaced(id, _, _, _) if config.check => {
+                outdated += 1;
+                warning(out, &result.path, result.line, &format!("{id} is outdated"))?;
+            }
+            _ => {}
+        }
+    }
+
+    if errors > 0 {
+        Err(BuildError::Failed { count: errors })
+    } else if outdated > 0 {
+        Err(BuildError::Outdated { count: outdated })
+    } else {
+        Ok(report)
+    }
+}
+
+fn rerun_if_changed(out: &mut impl Write, path: &Path) -> io::Result<()> {
+    writeln!(out, "cargo:rerun-if-changed={}", path.display())
+}
+
+/// Write a cargo warning, cargo warnings are single line messages.
+fn warning(
+    out: &mut impl Write,
+    path: &Path,
+    line: Option<usize>,
+    message: &str,
+) -> io::Result<()> {
+    let location = line.map_or_else(
+        || path.display().to_string(),
+        |line| format!("{}:{line}", path.display()),
+    );
+    writeln!(
+        out,
+        "cargo:warning=rdocs: {location}: {}",
+        message.replace(['\r', '\n'], " ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+
+    use tree_fs::Tree;
+
+    use super::*;
+    use crate::command::COMMAND_CACHE_FILE;
+
+    fn sync(config: &Config) -> (BuildResult<ReplaceReport>, String) {
+        let mut out = vec![];
+        let result = sync_docs_with_writer(config, &mut out);
+        (result, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn can_sync_docs() {
+        let res = Tree::default()
+            .add(
+                "src/lib.rs",
+                "// 📖 #START <id:sum>\nlet sum = 1 + 2;\n// 📖 #END\n",
+            )
+            .add("docs/README.md", "<!-- 📖sum -->\nold\n<!-- sum📖 -->\n")
+            .create()
+            .unwrap();
+        let base = res.canonicalize().unwrap();
+        let config = Config::new(res.join("src"), res.join("docs"));
+
+        let (result, out) = sync(&config);
+        assert!(matches!(result, Err(BuildError::Outdated { count: 1 })));
+        assert_eq!(
+            out,
+            format!(
+                "cargo:rerun-if-changed={src}\ncargo:rerun-if-changed={lib}\ncargo:rerun-if-changed={docs}\ncargo:rerun-if-changed={readme}\ncargo:warning=rdocs: {readme}:1: sum is outdated\n",
+                src = base.join("src").display(),
+                lib = base.join("src/lib.rs").display(),
+                docs = base.join("docs").display(),
+                readme = base.join("docs/README.md").display(),
+            )
+        );
+
+        let (result, out) = sync(&config.clone().with_write());
+        assert_eq!(result.unwrap().results.len(), 1);
+        assert!(!out.contains("cargo:warning"));
+
+        let (result, _) = sync(&config);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn check_without_command_cache() {
+        let res = Tree::default()
+            .add(
+                "rdocs.yaml",
+                r#"
+parser:
+  patterns: []
+  commands:
+  - id: version
+    run: echo 0.1.2
+collector:
+  includes:
+  excludes:
+"#,
+            )
+            .add("src/lib.rs", "")
+            .add(
+                "docs/README.md",
+                "<!-- 📖version -->\n0.1.2\n<!-- version📖 -->\n",
+            )
+            .create()
+            .unwrap();
+        let config =
+            Config::new(res.join("src"), res.join("docs")).with_config_file(res.join("rdocs.yaml"));
+
+        assert!(sync(&config).0.is_ok());
+        assert!(!res.join("src").join(COMMAND_CACHE_FILE).exists());
+    }
+
+    #[test]
+    fn sync_docs_with_errors() {
+        let res = Tree::default()
+            .add("src/lib.rs", "fn main() {}\n")
+            .create()
+            .unwrap();
+
+        let config = Config::new(res.join("src"), res.join("docs"));
+        assert!(matches!(
+            sync(&config).0,
+            Err(BuildError::Run(RunError::Collector(_)))
+        ));
+
+        let config = Config::new(res.join("src"), res.join("src"))
+            .with_config_file(res.join("missing.yaml"));
+        let (result, out) = sync(&config);
+        assert!(matches!(result, Err(BuildError::Config(_))));
+        assert!(out.starts_with("cargo:rerun-if-changed="));
+    }
+}
@@ -0,0 +1,443 @@
+//! A module for using command output as a content block.
+//!
+//! This module provides functionality to run configured commands locally and
+//! expose their output under an ID, so CLI usage examples like `--help`
+//! output stay aligned with the code. Outputs are recorded in a cache file so
+//! they can be reused without running the commands again.
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+    process::{Child, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::{CommandError, CommandResult},
+    parser::{ContentMetadata, ContentResults, Location},
+};
+
+/// The command cache file name, saved in the collect folder.
+pub const COMMAND_CACHE_FILE: &str = ".rdocs-commands.json";
+
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+const WAIT_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Represents a command that its output is used as a content block.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Command {
+    /// The content block ID.
+    pub id: String,
+    /// The command to run with the system shell.
+    pub run: String,
+    /// The working directory of the command, relative to the collect folder.
+    #[serde(default)]
+    pub cwd: Option<PathBuf>,
+    /// The command timeout in seconds.
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
+    /// Append the command stderr to the output.
+    #[serde(default)]
+    pub stderr: bool,
+    /// Use the command output even when the command exits with an error.
+    #[serde(default)]
+    pub allow_failure: bool,
+}
+
+const fn default_timeout() -> u64 {
+    DEFAULT_TIMEOUT_SECS
+}
+
+/// Enum representing how the command cache is used.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CacheMode {
+    /// Run the commands without using the cache.
+    #[default]
+    Off,
+    /// Run the commands and record the outputs in the cache.
+    Record,
+    /// Use the recorded outputs, and run only commands that are not recorded.
+    Use,
+    /// Like [`CacheMode::Use`], without saving the cache, so the collect
+    /// folder is not changed.
+    Read,
+}
+
+/// Represents a recorded command output.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct CachedOutput {
+    run: String,
+    cwd: Option<PathBuf>,
+    output: String,
+}
+
+/// Represents a command that could not run.
+#[derive(Debug)]
+pub struct Failure {
+    /// The content block ID of the command.
+    pub id: String,
+    /// The working directory of the command.
+    pub path: PathBuf,
+    pub error: CommandError,
+}
+
+/// Represents the recorded command outputs.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CommandCache {
+    outputs: BTreeMap<String, CachedOutput>,
+}
+
+impl Command {
+    /// Run the command from the given folder and returns the command output.
+    ///
+    /// # Errors
+    /// When the command could not start, exceeded the timeout or exits with an
+    /// error
+    pub fn run(&self, folder: &Path) -> CommandResult<String> {
+        let cwd = self
+            .cwd
+            .as_ref()
+            .map_or_else(|| folder.to_path_buf(), |cwd| folder.join(cwd));
+
+        let mut child = shell(&self.run)
+            .current_dir(cwd)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        let stdout = read_pipe(child.stdout.take());
+        let stderr = read_pipe(child.stderr.take());
+
+        let status = wait(&mut child, Duration::from_secs(self.timeout)).ok_or_else(|| {
+            CommandError::Timeout {
+                id: self.id.clone(),
+                timeout: self.timeout,
+            }
+        })??;
+
+        let stdout = stdout.join().unwrap_or_default();
+        let stderr = stderr.join().unwrap_or_default();
+
+        if !status.success() && !self.allow_failure {
+            return Err(CommandError::Failed {
+                id: self.id.clone(),
+                code: status.code(),
+                stderr: stderr.trim().to_string(),
+            });
+        }
+
+        let mut output = stdout;
+        if self.stderr {
+            output.push_str(&stderr);
+        }
+        Ok(output.trim_end().to_string())
+    }
+}
+
+impl CommandCache {
+    /// Load the command cache from the given folder. Returns an empty cache
+    /// when the cache file not exists.
+    ///
+    /// # Errors
+    /// When could not read or parse the cache file
+    pub fn load(folder: &Path) -> CommandResult<Self> {
+        let path = folder.join(COMMAND_CACHE_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_reader(File::open(path)?)?)
+    }
+
+    /// Save the command cache to the given folder.
+    ///
+    /// # Errors
+    /// When could not write the cache file
+    pub fn save(&self, folder: &Path) -> CommandResult<()> {
+        let file = File::create(folder.join(COMMAND_CACHE_FILE))?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+
+    /// Returns the recorded output of the given command. Outputs that were
+    /// recorded for a different command line or working directory are
+    /// ignored.
+    #[must_use]
+    pub fn get(&self, command: &Command) -> Option<&str> {
+        self.outputs
+            .get(&command.id)
+            .filter(|cached| cached.run == command.run && cached.cwd == command.cwd)
+            .map(|cached| cached.output.as_str())
+    }
+
+    /// Record the output of the given command. Returns `true` when the
+    /// recorded output changed.
+    pub fn set(&mut self, command: &Command, output: &str) -> bool {
+        if self.get(command) == Some(output) {
+            return false;
+        }
+        self.outputs.insert(
+            command.id.clone(),
+            CachedOutput {
+                run: command.run.clone(),
+                cwd: command.cwd.clone(),
+                output: output.to_string(),
+            },
+        );
+        true
+    }
+}
+
+/// Collect the output of the given commands as content results, and the
+/// commands that could not run. The cache file is saved only when a recorded
+/// output changed.
+#[must_use]
+pub fn collect(
+    commands: &[Command],
+    folder: &Path,
+    mode: CacheMode,
+) -> (Vec<ContentResults>, Vec<Failure>) {
+    if commands.is_empty() {
+        return (vec![], vec![]);
+    }
+
+    let mut cache = match mode {
+        CacheMode::Off => CommandCache::default(),
+        CacheMode::Record | CacheMode::Use | CacheMode::Read => CommandCache::load(folder)
+            .unwrap_or_else(|err| {
+                tracing::warn!(err = %err, "could not load command cache");
+                CommandCache::default()
+            }),
+    };
+
+    let mut results = vec![];
+    let mut failures = vec![];
+    let mut changed = false;
+    for command in commands {
+        let span = tracing::span!(tracing::Level::TRACE, "command", id = command.id);
+        let _guard = span.enter();
+
+        let cached = if matches!(mode, CacheMode::Use | CacheMode::Read) {
+            cache.get(command).map(ToString::to_string)
+        } else {
+            None
+        };
+
+        let path = command
+            .cwd
+            .as_ref()
+            .map_or_else(|| folder.to_path_buf(), |cwd| folder.join(cwd));
+
+        let output = if let Some(output) = cached {
+            tracing::trace!("using recorded command output");
+            output
+        } else {
+            match command.run(folder) {
+                Ok(output) => {
+                    changed |= cache.set(command, &output);
+                    output
+                }
+                Err(err) => {
+                    tracing::error!(err = %err, "could not run command");
+                    failures.push(Failure {
+                        id: command.id.clone(),
+                        path,
+                        error: err,
+                    });
+                    continue;
+                }
+            }
+        };
+
+        results.push(ContentResults {
+            metadata: ContentMetadata {
+                id: command.id.clone(),
+                lang: None,
+                attributes: BTreeMap::new(),
+                namespace: None,
+            },
+            data: output,
+            relative_path: String::new(),
+            location: Location {
+                path,
+                start_line: 0,
+                end_line: 0,
+            },
+        });
+    }
+
+    if matches!(mode, CacheMode::Record | CacheMode::Use) && changed {
+        if let Err(err) = cache.save(folder) {
+            tracing::warn!(err = %err, "could not save command cache");
+        }
+    }
+
+    (results, failures)
+}
+
+#[cfg(unix)]
+fn shell(run: &str) -> std::process::Command {
+    let mut command = std::process::Command::new("sh");
+    command.args(["-c", run]);
+    command
+}
+
+#[cfg(windows)]
+fn shell(run: &str) -> std::process::Command {
+    let mut command = std::process::Command::new("cmd");
+    command.args(["/C", run]);
+    command
+}
+
+/// Read the given pipe in a background thread, to prevent the child process
+/// from blocking on a full pipe.
+fn read_pipe(pipe: Option<impl Read + Send + 'static>) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut output = String::new();
+        if let Some(mut pipe) = pipe {
+            if let Err(err) = pipe.read_to_string(&mut output) {
+                tracing::debug!(err = %err, "could not read command output");
+            }
+        }
+        output
+    })
+}
+
+/// Wait for the child process to exit. The process is killed and `None` is
+/// returned when the timeout is exceeded.
+fn wait(child: &mut Child, timeout: Duration) -> Option<std::io::Result<std::process::ExitStatus>> {
+    let start = Instant::now();
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => return Some(Ok(status)),
+            Ok(None) if start.elapsed() >= timeout => {
+                if let Err(err) = child.kill() {
+                    tracing::debug!(err = %err, "could not kill command");
+                }
+                let _ = child.wait();
+                return None;
+            }
+            Ok(None) => thread::sleep(WAIT_INTERVAL),
+            Err(err) => return Some(Err(err)),
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+
+    use tree_fs::Tree;
+
+    use super::*;
+
+    fn command(id: &str, run: &str) -> Command {
+        Command {
+            id: id.to_string(),
+            run: run.to_string(),
+            cwd: None,
+            timeout: DEFAULT_TIMEOUT_SECS,
+            stderr: false,
+            allow_failure: false,
+        }
+    }
+
+    #[test]
+    fn can_run() {
+        let res = Tree::default().add("dir/file.txt", "").create().unwrap();
+
+        let mut ls = command("ls", "ls");
+        ls.cwd = Some(PathBuf::from("dir"));
+        assert_eq!(ls.run(&res).unwrap(), "file.txt");
+
+        let mut stderr = command("stderr", "echo out; echo err >&2");
+        assert_eq!(stderr.run(&res).unwrap(), "out");
+        stderr.stderr = true;
+        assert_eq!(stderr.run(&res).unwrap(), "out\nerr");
+    }
+
+    #[test]
+    fn run_with_error() {
+        let res = Tree::default().add("README.md", "").create().unwrap();
+
+        let mut failed = command("failed", "echo usage; exit 2");
+        assert!(matches!(
+            failed.run(&res),
+            Err(CommandError::Failed { code: Some(2), .. })
+        ));
+        failed.allow_failure = true;
+        assert_eq!(failed.run(&res).unwrap(), "usage");
+
+        let mut timeout = command("timeout", "sleep 5");
+        timeout.timeout = 0;
+        assert!(matches!(
+            timeout.run(&res),
+            Err(CommandError::Timeout { .. })
+        ));
+    }
+
+    #[test]
+    fn can_use_cache() {
+        let res = Tree::default().add("README.md", "").create().unwrap();
+        // prints the number of runs, recorded in a counter file
+        let commands = vec![command(
+            "counter",
+            "echo run >> counter.txt && wc -l < counter.txt | tr -d ' '",
+        )];
+
+        let (recorded, _) = collect(&commands, &res, CacheMode::Record);
+        let modified = std::fs::metadata(res.join(COMMAND_CACHE_FILE))
+            .and_then(|metadata| metadata.modified())
+            .unwrap();
+        let (cached, _) = collect(&commands, &res, CacheMode::Use);
+        let (fresh, _) = collect(&commands, &res, CacheMode::Off);
+
+        assert_eq!(recorded[0].data, cached[0].data);
+        assert_ne!(recorded[0].data, fresh[0].data);
+        // the cache is not saved when all the outputs were recorded
+        assert_eq!(
+            std::fs::metadata(res.join(COMMAND_CACHE_FILE))
+                .and_then(|metadata| metadata.modified())
+                .unwrap(),
+            modified
+        );
+    }
+
+    #[test]
+    fn read_cache() {
+        let res = Tree::default().add("README.md", "").create().unwrap();
+        let commands = vec![command(
+            "counter",
+            "echo run >> counter.txt && wc -l < counter.txt | tr -d ' '",
+        )];
+
+        let (read, _) = collect(&commands, &res, CacheMode::Read);
+        assert!(!res.join(COMMAND_CACHE_FILE).exists());
+
+        let (recorded, _) = collect(&commands, &res, CacheMode::Record);
+        let (cached, _) = collect(&commands, &res, CacheMode::Read);
+        assert_ne!(read[0].data, recorded[0].data);
+        assert_eq!(recorded[0].data, cached[0].data);
+    }
+
+    #[test]
+    fn collect_with_failures() {
+        let res = Tree::default().add("README.md", "").create().unwrap();
+        let commands = vec![
+            command("version", "echo 0.1.2"),
+            command("failed", "exit 3"),
+        ];
+
+        let (results, failures) = collect(&commands, &res, CacheMode::Off);
+        assert_eq!(results.len(), 1);
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].id, "failed");
+        assert!(matches!(
+            failures[0].error,
+            CommandError::Failed { code: Some(3), .. }
+        ));
+    }
+}
@@ -0,0 +1,98 @@
+//! A module for loading the rdocs configuration file.
+//!
+//! The configuration file is a YAML file with the parser, collector,
+//! verification and export settings. All the sections except `parser` and
+//! `collector` are optional.
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    collect,
+    errors::{ConfigError, ConfigResult},
+    out, parser, verify,
+};
+
+/// Represents the rdocs configuration.
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct Config {
+    pub parser: parser::Config,
+    pub collector: collect::Config,
+    #[serde(default)]
+    pub verify: verify::Config,
+    #[serde(default)]
+    pub export: out::Layout,
+}
+
+impl Config {
+    /// Load the configuration from the given YAML file.
+    ///
+    /// # Errors
+    /// When the file could not be read or is not a valid configuration, for
+    /// example a metadata pattern without the `id` named group
+    pub fn from_path(path: &Path) -> ConfigResult<Self> {
+        let rdr = std::fs::File::open(path)?;
+        let config: Self = serde_yaml::from_reader(rdr)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Load the configuration from the given YAML file, or fallback to the
+    /// default configuration when no file is given.
+    ///
+    /// # Errors
+    /// When the file could not be read or is not a valid configuration
+    pub fn load(path: Option<&Path>) -> ConfigResult<Self> {
+        path.map_or_else(|| Ok(Self::default()), Self::from_path)
+    }
+
+    /// Checks that every metadata pattern captures the block ID.
+    fn validate(&self) -> ConfigResult<()> {
+        if let Some(pattern) = self
+            .parser
+            .patterns()
+            .iter()
+            .find(|pattern| !pattern.has_id_group())
+        {
+            return Err(ConfigError::MissingIdGroup {
+                metadata: pattern.metadata.to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use tree_fs::Tree;
+
+    use super::*;
+
+    const CONFIG: &str = r"
+parser:
+  patterns:
+  - start: START
+    end: END
+    metadata: 'id=(?P<id>\w+)'
+    cleanups: []
+collector:
+  includes:
+  excludes:
+";
+
+    #[test]
+    fn can_load_from_path() {
+        let res = Tree::default()
+            .add("rdocs.yaml", CONFIG)
+            .add("no-id.yaml", &CONFIG.replace("(?P<id>", "(?P<name>"))
+            .create()
+            .unwrap();
+
+        assert!(Config::from_path(&res.join("rdocs.yaml")).is_ok());
+        assert!(matches!(
+            Config::from_path(&res.join("no-id.yaml")),
+            Err(ConfigError::MissingIdGroup { metadata }) if metadata == r"id=(?P<name>\w+)"
+        ));
+    }
+}
@@ -0,0 +1,171 @@
+//! A module for creating unified diffs from replace results.
+//!
+//! This module provides functionality to show the changes that the replace
+//! operation applies to the target files, and to create a patch that can be
+//! applied with `git apply`.
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    io,
+    path::{Path, PathBuf},
+};
+
+use similar::TextDiff;
+
+use crate::replacer::{ReplaceResult, ReplaceStatus};
+
+const COLOR_RED: &str = "\x1b[31m";
+const COLOR_GREEN: &str = "\x1b[32m";
+const COLOR_CYAN: &str = "\x1b[36m";
+const COLOR_BOLD: &str = "\x1b[1m";
+const COLOR_RESET: &str = "\x1b[0m";
+
+/// Represents the content of a file before and after the replacement.
+#[derive(Debug)]
+pub struct FileDiff {
+    pub path: PathBuf,
+    pub before: String,
+    pub after: String,
+}
+
+impl FileDiff {
+    /// Creates a [`FileDiff`] for each file with replaced blocks. The content
+    /// before the replacement is read from the file, so the results should be
+    /// created without saving the changes (see
+    /// [`crate::replacer::Replace::stats`]).
+    ///
+    /// # Errors
+    ///
+    /// When could not read one of the replaced files
+    pub fn from_results(results: &[ReplaceResult]) -> io::Result<Vec<Self>> {
+        let mut files: BTreeMap<&Path, &str> = BTreeMap::new();
+        for result in results {
+            if let ReplaceStatus::Replaced(_, content, _, _) = &result.status {
+                // each replacement holds the file content with all the previous
+                // replacements, so the last one is the final file content.
+                files.insert(result.path.as_path(), content);
+            }
+        }
+
+        files
+            .into_iter()
+            .map(|(path, after)| {
+                // rendered template targets may not exist yet
+                let before = if path.exists() {
+                    std::fs::read_to_string(path)?
+                } else {
+                    String::new()
+                };
+                Ok(Self {
+                    path: path.to_path_buf(),
+                    before,
+                    after: after.to_string(),
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the unified diff of the file with the given number of context
+    /// lines. The diff headers are relative to the given base folder when the
+    /// file is located under it.
+    #[must_use]
+    pub fn unified(&self, base: Option<&Path>, context: usize) -> String {
+        let path = base
+            .and_then(|base| self.path.strip_prefix(base).ok())
+            .unwrap_or(&self.path)
+            .display()
+            .to_string();
+        let path = path.trim_start_matches('/');
+
+        let diff = TextDiff::from_lines(&self.before, &self.after);
+        let hunks = diff
+            .unified_diff()
+            .context_radius(context)
+            .header(&format!("a/{path}"), &format!("b/{path}"))
+            .to_string();
+
+        format!("diff --git a/{path} b/{path}\n{hunks}")
+    }
+}
+
+/// Returns the unified diff hunks between two blocks, without file headers.
+#[must_use]
+pub fn block(before: &str, after: &str, context: usize) -> String {
+    let before = format!("{before}\n");
+    let after = format!("{after}\n");
+    TextDiff::from_lines(&before, &after)
+        .unified_diff()
+        .context_radius(context)
+        .to_string()
+}
+
+/// Creates a single patch from all the given file diffs.
+#[must_use]
+pub fn patch(diffs: &[FileDiff], base: Option<&Path>, context: usize) -> String {
+    diffs
+        .iter()
+        .map(|diff| diff.unified(base, context))
+        .collect::<String>()
+}
+
+/// Adds terminal colors to the given unified diff.
+#[must_use]
+pub fn colorize(diff: &str) -> String {
+    diff.lines().fold(String::new(), |mut out, line| {
+        let color =
+            if line.starts_with("diff --git") || line.starts_with("+++") || line.starts_with("---")
+            {
+                COLOR_BOLD
+            } else if line.starts_with("@@") {
+                COLOR_CYAN
+            } else if line.starts_with('+') {
+                COLOR_GREEN
+            } else if line.starts_with('-') {
+                COLOR_RED
+            } else {
+                let _ = writeln!(out, "{line}");
+                return out;
+            };
+        let _ = writeln!(out, "{color}{line}{COLOR_RESET}");
+        out
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use insta::assert_snapshot;
+
+    use super::*;
+
+    fn get_diff() -> FileDiff {
+        FileDiff {
+            path: PathBuf::from("/project/docs/README.md"),
+            before: "# Title\n<!-- 📖quick-start -->\nold\n<!-- quick-start📖 -->\nfooter\n"
+                .to_string(),
+            after: "# Title\n<!-- 📖quick-start -->\nnew\n<!-- quick-start📖 -->\nfooter\n"
+                .to_string(),
+        }
+    }
+
+    #[test]
+    fn can_create_unified_diff() {
+        assert_snapshot!(get_diff().unified(Some(Path::new("/project")), 1));
+    }
+
+    #[test]
+    fn can_create_block_diff() {
+        assert_eq!(
+            block("fn main() {\n}", "fn main() {\n    run();\n}", 1),
+            "@@ -1,2 +1,3 @@\n fn main() {\n+    run();\n }\n"
+        );
+    }
+
+    #[test]
+    fn can_colorize() {
+        let diff = colorize(&get_diff().unified(None, 0));
+        assert!(diff.contains(&format!("{COLOR_RED}-old{COLOR_RESET}")));
+        assert!(diff.contains(&format!("{COLOR_GREEN}+new{COLOR_RESET}")));
+        assert!(diff.contains(&format!("{COLOR_BOLD}--- a/project/docs/README.md")));
+    }
+}
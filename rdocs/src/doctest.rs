@@ -0,0 +1,217 @@
+//! A module for generating tests from extracted Rust blocks.
+//!
+//! This module renders the extracted Rust blocks as a test file, so blocks
+//! injected into the documentation are exercised by `cargo test` even when
+//! they were extracted from non-test code.
+//!
+//! # Styles
+//!
+//! - [`Style::Test`]: a Rust file with a `#[test]` function per block, for
+//!   example `tests/snippets.rs`.
+//! - [`Style::Doctest`]: a markdown file with a `rust` code block per block,
+//!   that runs as doctests when it is included in the crate documentation:
+//!
+//! ```rust,ignore
+//! #[doc = include_str!("../docs/snippets.md")]
+//! #[cfg(doctest)]
+//! pub struct Snippets;
+//! ```
+use std::{collections::HashSet, fmt::Write, path::Path};
+
+use crate::parser::ContentResults;
+
+const HEADER: &str = "Generated by rdocs from the extracted Rust blocks. Do not edit manually.";
+
+/// The Rust keywords, that are not valid function names.
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl",
+    "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "static", "struct", "super", "trait", "true", "try", "type", "typeof",
+    "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// Enum representing the generated test styles.
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[derive(Clone, Copy, Debug, Default)]
+pub enum Style {
+    /// Generate a Rust file with a `#[test]` function per block.
+    #[default]
+    Test,
+    /// Generate a markdown file with a doctest per block.
+    Doctest,
+}
+
+/// Render the given blocks with the given style. Paths are displayed relative
+/// to `base`. The blocks are sorted by location and ID, so the output does not
+/// depend on the extraction order.
+#[must_use]
+pub fn render(contents: &[&ContentResults], base: &Path, style: Style) -> String {
+    let mut contents = contents.to_vec();
+    contents.sort_by(|a, b| {
+        (&a.location.path, a.location.start_line, &a.metadata.id).cmp(&(
+            &b.location.path,
+            b.location.start_line,
+            &b.metadata.id,
+        ))
+    });
+
+    match style {
+        Style::Test => render_tests(&contents, base),
+        Style::Doctest => render_doctests(&contents, base),
+    }
+}
+
+fn render_tests(contents: &[&ContentResults], base: &Path) -> String {
+    let mut out = format!("// {HEADER}\n#![allow(unused)]\n");
+    let mut names = HashSet::new();
+
+    for content in contents {
+        let mut name = test_name(&content.metadata.id);
+        let mut index = 1;
+        while !names.insert(name.clone()) {
+            index += 1;
+            name = format!("{}_{index}", test_name(&content.metadata.id));
+        }
+
+        let _ = write!(
+            out,
+            "\n/// Block `{}` from `{}`.\n#[test]\nfn {name}() {{\n",
+            content.metadata.id,
+            source(content, base)
+        );
+        for line in dedent(&content.data).lines() {
+            if line.trim().is_empty() {
+                out.push('\n');
+            } else {
+                let _ = writeln!(out, "    {line}");
+            }
+        }
+        if content.data.contains("fn main") {
+            out.push_str("    main();\n");
+        }
+        out.push_str("}\n");
+    }
+
+    out
+}
+
+fn render_doctests(contents: &[&ContentResults], base: &Path) -> String {
+    let mut out = format!("<!-- {HEADER} -->\n");
+
+    for content in contents {
+        let _ = writeln!(
+            out,
+            "\n## `{}`\n\nSource: `{}`\n\n```rust\n{}\n```",
+            content.metadata.id,
+            source(content, base),
+            dedent(&content.data)
+        );
+    }
+
+    out
+}
+
+/// Returns the block source location, relative to `base`.
+fn source(content: &ContentResults, base: &Path) -> String {
+    let path = content
+        .location
+        .path
+        .strip_prefix(base)
+        .unwrap_or(&content.location.path);
+    format!("{}:{}", path.display(), content.location.start_line)
+}
+
+/// Remove the common indentation of the block lines. The first line is
+/// ignored, since the extracted content starts at the first non whitespace
+/// character.
+fn dedent(data: &str) -> String {
+    let indent = data
+        .lines()
+        .skip(1)
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or_default();
+
+    data.lines()
+        .enumerate()
+        .map(|(index, line)| {
+            if index == 0 || line.trim().is_empty() {
+                line.trim_start()
+            } else {
+                line.get(indent..).unwrap_or_else(|| line.trim_start())
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Convert the given block ID to a valid test function name.
+fn test_name(id: &str) -> String {
+    let mut name = id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic()) || KEYWORDS.contains(&name.as_str()) {
+        name.insert_str(0, "block_");
+    }
+    name
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::path::PathBuf;
+
+    use insta::assert_snapshot;
+
+    use super::*;
+
+    fn content(id: &str, data: &str) -> ContentResults {
+        let mut content = ContentResults::new_for_test(id, data, "/repo/src/lib.rs", 10, 14);
+        content.metadata.lang = Some("rust".to_string());
+        content
+    }
+
+    #[test]
+    fn can_render() {
+        let contents = [
+            content("add-numbers", "let sum = 1 + 2;\n\nassert_eq!(sum, 3);"),
+            content(
+                "Add Numbers",
+                "let sum = 2 + 2;\n        assert_eq!(sum, 4);",
+            ),
+            content("2-main", "fn main() {\n    println!(\"hi\");\n}"),
+            content("type", "let kind = \"type\";"),
+        ];
+        let contents = contents.iter().collect::<Vec<_>>();
+
+        assert_snapshot!(render(&contents, Path::new("/repo"), Style::Test));
+        assert_snapshot!(render(&contents, Path::new("/repo"), Style::Doctest));
+    }
+
+    #[test]
+    fn render_in_location_order() {
+        let mut first = content("first", "let a = 1;");
+        first.location.start_line = 2;
+        let mut other = content("first", "let b = 2;");
+        other.location.path = PathBuf::from("/repo/src/other.rs");
+        let last = content("last", "let c = 3;");
+
+        let rendered = render(&[&other, &last, &first], Path::new("/repo"), Style::Test);
+        assert_eq!(
+            rendered,
+            render(&[&first, &last, &other], Path::new("/repo"), Style::Test)
+        );
+        let first_index = rendered.find("fn first()").unwrap();
+        assert!(first_index < rendered.find("fn last()").unwrap());
+        assert!(rendered.find("fn last()").unwrap() < rendered.find("fn first_2()").unwrap());
+    }
+}
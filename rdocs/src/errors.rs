@@ -12,6 +12,12 @@ pub enum ParseError {
         pattern_start: String,
         pattern_end: String,
     },
+
+    #[error("included block not found: {id}")]
+    IncludeNotFound { id: String },
+
+    #[error("include cycle detected: {ids}")]
+    IncludeCycle { ids: String },
 }
 #[derive(thiserror::Error, Debug)]
 pub enum ReplacerError {
@@ -25,5 +31,159 @@ pub enum ReplacerError {
     CaptureNotFound { index: i32 },
 }
 
+#[derive(thiserror::Error, Debug)]
+pub enum SyncError {
+    #[error(transparent)]
+    IO(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    Replacer(#[from] ReplacerError),
+
+    #[error("pattern not found for block: {id}")]
+    PatternNotFound { id: String },
+
+    #[error("block contains nested blocks: {id}")]
+    NestedBlock { id: String },
+
+    #[error("block contains directives: {id}")]
+    Directive { id: String },
+
+    #[error("block contains variables: {id}")]
+    Variable { id: String },
+
+    #[error("generated block can not be pulled: {id}")]
+    GeneratedBlock { id: String },
+
+    #[error("could not reverse cleanups of block: {id}")]
+    ReverseCleanup { id: String },
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum CommandError {
+    #[error(transparent)]
+    IO(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    #[error("command timed out after {timeout}s: {id}")]
+    Timeout { id: String, timeout: u64 },
+
+    #[error("command failed with exit code {code:?}: {id}: {stderr}")]
+    Failed {
+        id: String,
+        code: Option<i32>,
+        stderr: String,
+    },
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum VerifyError {
+    #[error(transparent)]
+    IO(#[from] std::io::Error),
+
+    #[error("could not run {program}: {err}")]
+    Command {
+        program: String,
+        err: std::io::Error,
+    },
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ExportError {
+    #[error(transparent)]
+    IO(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    #[error("invalid export path of block {id}: {path}")]
+    InvalidPath { id: String, path: String },
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ResultsError {
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    Yaml(#[from] serde_yaml::Error),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum BookError {
+    #[error("chapter `{title}` has the same page as another chapter: {slug}")]
+    DuplicateChapter { title: String, slug: String },
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum TemplateError {
+    #[error(transparent)]
+    IO(#[from] std::io::Error),
+
+    #[error("snippet not found: {id}")]
+    SnippetNotFound { id: String },
+
+    #[error("invalid template filter: {filter}")]
+    InvalidFilter { filter: String },
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ConfigError {
+    #[error("could not read config file: {0}")]
+    IO(#[from] std::io::Error),
+
+    #[error("invalid config file: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+
+    #[error("metadata pattern has no `id` named group: {metadata}")]
+    MissingIdGroup { metadata: String },
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum RunError {
+    #[error("could not init collector: {0}")]
+    Collector(std::io::Error),
+
+    #[error("could not verify blocks: {0}")]
+    Verify(#[from] VerifyError),
+
+    #[error("found {} blocks that failed verification", failures.len())]
+    VerifyFailed {
+        failures: Vec<crate::verify::Failure>,
+    },
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum BuildError {
+    #[error(transparent)]
+    IO(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+
+    #[error(transparent)]
+    Run(#[from] RunError),
+
+    #[error("failed to replace {count} documentation blocks")]
+    Failed { count: usize },
+
+    #[error("found {count} outdated documentation blocks")]
+    Outdated { count: usize },
+}
+
 pub type ParserResult<T> = std::result::Result<T, ParseError>;
 pub type ReplacerResult<T> = std::result::Result<T, ReplacerError>;
+pub type SyncResult<T> = std::result::Result<T, SyncError>;
+pub type CommandResult<T> = std::result::Result<T, CommandError>;
+pub type VerifyResult<T> = std::result::Result<T, VerifyError>;
+pub type ExportResult<T> = std::result::Result<T, ExportError>;
+pub type ResultsResult<T> = std::result::Result<T, ResultsError>;
+pub type BookResult<T> = std::result::Result<T, BookError>;
+pub type TemplateResult<T> = std::result::Result<T, TemplateError>;
+pub type ConfigResult<T> = std::result::Result<T, ConfigError>;
+pub type RunResult<T> = std::result::Result<T, RunError>;
+pub type BuildResult<T> = std::result::Result<T, BuildError>;
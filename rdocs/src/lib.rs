@@ -47,11 +47,27 @@
 //![GitHub Repository](https://github.com/kaplanelad/rdocs/releases/latest)
 // 📖 #END
 
+pub mod book;
+pub mod build;
 #[cfg(feature = "cli")]
 pub mod cli;
 pub mod collect;
+pub mod command;
+pub mod config;
+pub mod diff;
+pub mod doctest;
 pub mod errors;
+pub mod markdown;
 pub mod out;
 pub mod parser;
 pub mod pattern;
 pub mod replacer;
+pub mod report;
+pub mod results;
+pub mod run;
+pub mod sync;
+pub mod template;
+pub mod vars;
+pub mod verify;
+
+pub use run::{run_collect, run_replace};
@@ -0,0 +1,318 @@
+//! A module for extracting Markdown sections as content blocks.
+//!
+//! When enabled, every heading section of a Markdown file becomes a content
+//! block with an ID derived from the heading slug, so one canonical doc file
+//! can feed multiple READMEs and book chapters. A section ends at the next
+//! heading of the same or a higher level. Blocks can also be delimited with
+//! HTML comments (see [`Preset::Html`]).
+//!
+//! ```markdown
+//! ## Quick Start          <- block ID: `quick-start`
+//! Run `cargo install rdocs`.
+//!
+//! <!-- 📖 #START <id:usage> -->
+//! rdocs collect .
+//! <!-- 📖 #END -->
+//! ```
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+    sync::LazyLock,
+};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    parser::{ContentMetadata, ContentResults, Location},
+    pattern::Preset,
+};
+
+/// Matches an ATX heading, for example `## Quick Start`
+static HEADING_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^ {0,3}(#{1,6})\s+(.*?)(?:\s+#+)?\s*$").unwrap());
+/// Matches a fenced code block delimiter
+static FENCE_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\s*(```|~~~)").unwrap());
+
+/// Represents the Markdown source configuration.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Config {
+    /// The file extensions of Markdown source files.
+    #[serde(default = "default_extensions")]
+    pub extensions: Vec<String>,
+    /// The deepest heading level that starts a block.
+    #[serde(default = "default_max_level")]
+    pub max_level: usize,
+    /// Include the heading line in the block content.
+    #[serde(default)]
+    pub include_heading: bool,
+}
+
+fn default_extensions() -> Vec<String> {
+    vec!["md".to_string(), "markdown".to_string()]
+}
+
+const fn default_max_level() -> usize {
+    6
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            extensions: default_extensions(),
+            max_level: default_max_level(),
+            include_heading: false,
+        }
+    }
+}
+
+/// Represents a heading found in a Markdown file.
+struct Heading {
+    level: usize,
+    slug: String,
+    /// The heading line index.
+    index: usize,
+}
+
+impl Config {
+    /// Checks if the given file is a Markdown source file.
+    #[must_use]
+    pub fn is_markdown(&self, path: &Path) -> bool {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| {
+                self.extensions
+                    .iter()
+                    .any(|e| e.eq_ignore_ascii_case(extension))
+            })
+    }
+
+    /// Extracts the heading sections of the given file.
+    #[must_use]
+    pub fn extract_sections(&self, path: &Path) -> Vec<ContentResults> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => self.sections(path, &content),
+            Err(err) => {
+                tracing::error!(err = %err, "could not read markdown file");
+                vec![]
+            }
+        }
+    }
+
+    /// Returns the heading sections of the given content. Empty sections are
+    /// skipped.
+    #[must_use]
+    pub fn sections(&self, path: &Path, content: &str) -> Vec<ContentResults> {
+        let lines = content.lines().collect::<Vec<_>>();
+        let headings = headings(&lines);
+        let markers = Preset::Html.pattern();
+
+        let mut results = vec![];
+        for (position, heading) in headings.iter().enumerate() {
+            if heading.level > self.max_level {
+                continue;
+            }
+
+            let end_index = headings[position + 1..]
+                .iter()
+                .find(|next| next.level <= heading.level)
+                .map_or(lines.len(), |next| next.index);
+
+            let start_index = if self.include_heading {
+                heading.index
+            } else {
+                heading.index + 1
+            };
+
+            let section = lines[start_index..end_index]
+                .iter()
+                .filter(|line| !markers.start_with(line) && !markers.end_with(line))
+                .copied()
+                .collect::<Vec<_>>()
+                .join("\n");
+            let data = section.trim_matches('\n').trim_end();
+
+            if data.trim().is_empty() {
+                tracing::trace!(slug = heading.slug, "skip empty section");
+                continue;
+            }
+
+            results.push(ContentResults {
+                metadata: ContentMetadata {
+                    id: heading.slug.clone(),
+                    lang: None,
+                    attributes: BTreeMap::new(),
+                    namespace: None,
+                },
+                data: data.to_string(),
+                relative_path: String::new(),
+                location: Location {
+                    path: path.to_path_buf(),
+                    start_line: heading.index + 1,
+                    // the exclusive 0-based end index is the 1-based number
+                    // of the last section line
+                    end_line: end_index,
+                },
+            });
+        }
+
+        results
+    }
+}
+
+/// Returns the headings of the given lines, skipping fenced code blocks.
+/// Duplicate slugs get a numeric suffix, like GitHub heading anchors.
+fn headings(lines: &[&str]) -> Vec<Heading> {
+    let mut headings = vec![];
+    let mut slugs: HashMap<String, usize> = HashMap::new();
+    let mut fence: Option<&str> = None;
+
+    for (index, line) in lines.iter().enumerate() {
+        if let Some(captures) = FENCE_RE.captures(line) {
+            let delimiter = captures.get(1).map_or("", |m| m.as_str());
+            match fence {
+                Some(open) if open == delimiter => fence = None,
+                Some(_) => {}
+                None => fence = Some(delimiter),
+            }
+            continue;
+        }
+        if fence.is_some() {
+            continue;
+        }
+
+        let Some(captures) = HEADING_RE.captures(line) else {
+            continue;
+        };
+
+        let base = slug(&captures[2]);
+        let count = slugs.entry(base.clone()).or_default();
+        let slug = if *count == 0 {
+            base
+        } else {
+            format!("{base}-{count}")
+        };
+        *count += 1;
+
+        headings.push(Heading {
+            level: captures[1].len(),
+            slug,
+            index,
+        });
+    }
+
+    headings
+}
+
+/// Converts the given heading title to a slug, like GitHub heading anchors.
+#[must_use]
+pub fn slug(title: &str) -> String {
+    title
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                Some(c)
+            } else if c.is_whitespace() {
+                Some('-')
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use insta::assert_debug_snapshot;
+
+    use super::*;
+
+    const CONTENT: &str = r"# Rdocs
+
+Code Documentation Made Simple.
+
+## Quick Start
+
+Run the `collect` command:
+
+```sh
+# not a heading
+rdocs collect .
+```
+
+### Options
+
+<!-- 📖 #START <id:log-level> -->
+Use `--log-level` to change the log level.
+<!-- 📖 #END -->
+
+## Quick Start
+
+## FAQ & Support!
+Open an issue.
+";
+
+    #[test]
+    fn can_slug() {
+        assert_eq!(slug("Quick Start"), "quick-start");
+        assert_eq!(slug("FAQ & Support!"), "faq--support");
+        assert_eq!(slug("  `collect` command "), "collect-command");
+    }
+
+    #[test]
+    fn can_extract_sections() {
+        let config = Config {
+            max_level: 2,
+            ..Config::default()
+        };
+        assert_debug_snapshot!(config.sections(Path::new("README.md"), CONTENT));
+    }
+
+    #[test]
+    fn can_get_section_lines() {
+        let sections = Config::default().sections(Path::new("README.md"), CONTENT);
+        let lines = CONTENT.lines().collect::<Vec<_>>();
+        let options = &sections[2];
+        assert_eq!(lines[options.location.start_line - 1], "### Options");
+        // the end line is the last section line, before the next heading
+        assert_eq!(lines[options.location.end_line - 1], "");
+        assert_eq!(lines[options.location.end_line], "## Quick Start");
+    }
+
+    #[test]
+    fn can_match_markdown_extensions() {
+        let config = Config::default();
+        assert!(config.is_markdown(Path::new("README.md")));
+        assert!(config.is_markdown(Path::new("docs/GUIDE.MD")));
+        assert!(!config.is_markdown(Path::new("src/lib.rs")));
+    }
+
+    #[test]
+    fn can_extract_sections_with_heading() {
+        let config = Config {
+            include_heading: true,
+            ..Config::default()
+        };
+        let sections = config.sections(Path::new("README.md"), CONTENT);
+        assert_eq!(
+            sections
+                .iter()
+                .map(|section| section.metadata.id.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "rdocs",
+                "quick-start",
+                "options",
+                "quick-start-1",
+                "faq--support"
+            ]
+        );
+        assert_eq!(
+            sections[2].data,
+            "### Options\n\nUse `--log-level` to change the log level."
+        );
+    }
+}
@@ -2,21 +2,102 @@
 //!
 //! This module provides functionality to export content to different formats
 //! and destinations.
+//!
+//! When only the content is exported to a folder, each block is written to a
+//! file named by the [`Layout`] template, and a manifest of the written files
+//! is saved in the folder. Files of a previous export that were not written
+//! again are stale, and are removed when pruning is enabled.
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::prelude::*,
+    path::{Component, Path, PathBuf},
+};
 
-use std::{fs::File, io::prelude::*, path::PathBuf};
+use serde::{Deserialize, Serialize};
 
-use crate::parser::ContentResults;
+use crate::{
+    book,
+    errors::{ExportError, ExportResult},
+    parser::ContentResults,
+};
 
 /// Constant holding the default file name used when exporting to a file without
 /// a specified name.
 const DEFAULT_FILE_NAME: &str = "rdocs";
 
+/// The manifest file name, saved in the output folder.
+pub const MANIFEST_FILE: &str = ".rdocs-manifest.json";
+
+/// The file extension of blocks without a language or a source extension.
+const DEFAULT_EXTENSION: &str = "txt";
+
+/// The placeholders of the [`Layout`] file path template.
+const ID_PLACEHOLDER: &str = "{id}";
+const EXT_PLACEHOLDER: &str = "{ext}";
+const LANG_PLACEHOLDER: &str = "{lang}";
+const NAMESPACE_PLACEHOLDER: &str = "{namespace}";
+const SOURCE_STEM_PLACEHOLDER: &str = "{source_stem}";
+
+/// Characters that are replaced in the template values, since they are path
+/// separators or not allowed in file names.
+const INVALID_FILENAME_CHARS: &[char] = &['/', '\\', ':', '*', '?', '"', '<', '>', '|'];
+
 /// Enum representing different export options.
 pub enum Content {
     /// Export only the content in the patter
-    Only(Output),
+    Only(Output, Layout),
     /// Export all extractor data and metadata
     All(Output, Format),
+    /// Export a book of the content, a page per chapter (see [`book`])
+    Book(Output, book::Layout, book::Style),
+}
+
+/// Represents the layout of the files exported with [`Content::Only`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Layout {
+    /// The file path template, relative to the output folder. Supports the
+    /// `{id}`, `{ext}`, `{lang}`, `{namespace}` and `{source_stem}`
+    /// placeholders, for example `{source_stem}/{id}.{ext}`.
+    #[serde(default = "default_filename")]
+    pub filename: String,
+    /// The file extension of each block language, in addition to the
+    /// built-in extensions. For example `python: py`.
+    #[serde(default)]
+    pub extensions: BTreeMap<String, String>,
+    /// Remove the stale files of previous exports.
+    #[serde(default)]
+    pub prune: bool,
+}
+
+fn default_filename() -> String {
+    ID_PLACEHOLDER.to_string()
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            filename: default_filename(),
+            extensions: BTreeMap::new(),
+            prune: false,
+        }
+    }
+}
+
+/// Represents the files written by an export to a folder.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    pub files: Vec<ManifestFile>,
+}
+
+/// Represents a file written by an export.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestFile {
+    /// The file path relative to the output folder, with `/` separators.
+    pub path: String,
+    /// The block ID.
+    pub id: String,
 }
 
 /// Enum representing different output options.
@@ -28,12 +109,28 @@ pub enum Output {
 }
 
 /// Enum representing different export formats.
-#[derive(clap::ValueEnum, Clone)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[derive(Clone)]
 pub enum Format {
     /// Export in JSON format.
     Json,
     /// Export in YAML format.
     Yaml,
+    /// Export in TOML format.
+    Toml,
+    /// Export in newline-delimited JSON format, a block per line.
+    Ndjson,
+    /// Export in CSV format, a block per row.
+    Csv,
+    /// Export a Markdown catalog, a section per block.
+    Markdown,
+}
+
+/// Represents the TOML document of the exported blocks, since a TOML
+/// document can not be an array.
+#[derive(Serialize)]
+struct TomlDocument<'a> {
+    blocks: &'a [ContentResults],
 }
 
 impl Format {
@@ -43,7 +140,310 @@ impl Format {
         match self {
             Self::Json => "json",
             Self::Yaml => "yaml",
+            Self::Toml => "toml",
+            Self::Ndjson => "ndjson",
+            Self::Csv => "csv",
+            Self::Markdown => "md",
+        }
+    }
+
+    /// Render the given content results.
+    ///
+    /// # Errors
+    ///
+    /// When could not serialize the results to the given format
+    pub fn render(&self, results: &[ContentResults]) -> Result<String, Box<dyn std::error::Error>> {
+        Ok(match self {
+            Self::Json => serde_json::to_string_pretty(results)?,
+            Self::Yaml => serde_yaml::to_string(results)?,
+            Self::Toml => toml::to_string(&TomlDocument { blocks: results })?,
+            Self::Ndjson => {
+                let mut out = String::new();
+                for result in results {
+                    out.push_str(&serde_json::to_string(result)?);
+                    out.push('\n');
+                }
+                out
+            }
+            Self::Csv => csv(results),
+            Self::Markdown => markdown(results),
+        })
+    }
+}
+
+fn csv(results: &[ContentResults]) -> String {
+    let mut out = String::from("id,lang,path,line,data\n");
+    for result in results {
+        let row = [
+            result.metadata.id.clone(),
+            result.metadata.lang.clone().unwrap_or_default(),
+            result.relative_path.clone(),
+            result.location.start_line.to_string(),
+            result.data.clone(),
+        ];
+        out.push_str(
+            &row.iter()
+                .map(|field| escape_csv(field))
+                .collect::<Vec<_>>()
+                .join(","),
+        );
+        out.push('\n');
+    }
+    out
+}
+
+fn escape_csv(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn markdown(results: &[ContentResults]) -> String {
+    let mut out = String::from("# Blocks\n");
+    for result in results {
+        let path = &result.relative_path;
+        let line = result.location.start_line;
+        out.push_str(&markdown_section(
+            result,
+            &format!("[{path}:{line}]({path}#L{line})"),
+        ));
+    }
+    out
+}
+
+/// Returns a Markdown code fence longer than any backtick run in the given
+/// content, so the content can not close the fenced code block.
+pub(crate) fn fence(content: &str) -> String {
+    let longest = content
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or_default();
+    "`".repeat(longest.max(2) + 1)
+}
+
+/// Render the given block as a Markdown section, with the given source
+/// reference and the block content in a fenced code block.
+pub(crate) fn markdown_section(result: &ContentResults, source: &str) -> String {
+    let fence = fence(&result.data);
+
+    format!(
+        "\n## `{}`\n\nSource: {source}\n\n{fence}{}\n{}\n{fence}\n",
+        result.metadata.id,
+        result.metadata.lang.as_deref().unwrap_or_default(),
+        result.data
+    )
+}
+
+impl Layout {
+    /// Returns the file path of the given block, relative to the output
+    /// folder.
+    ///
+    /// # Errors
+    /// When the path is empty, absolute or contains `.` or `..` components,
+    /// or the block id or namespace contains a `..` segment
+    pub fn path(&self, result: &ContentResults) -> ExportResult<PathBuf> {
+        let namespace = result.metadata.namespace.as_deref().unwrap_or_default();
+        let source_stem = result
+            .location
+            .path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let rendered = self
+            .filename
+            .replace(ID_PLACEHOLDER, &sanitize(&result.metadata.id))
+            .replace(EXT_PLACEHOLDER, &sanitize(&self.extension(result)))
+            .replace(
+                LANG_PLACEHOLDER,
+                &sanitize(result.metadata.lang.as_deref().unwrap_or_default()),
+            )
+            .replace(NAMESPACE_PLACEHOLDER, &sanitize(namespace))
+            .replace(SOURCE_STEM_PLACEHOLDER, &sanitize(&source_stem));
+
+        // empty placeholder values leave empty path segments, which are
+        // skipped
+        let path = rendered
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect::<PathBuf>();
+        // the separators of the id and namespace are replaced, but their
+        // parent segments are rejected instead of silently renamed
+        let has_parent = [result.metadata.id.as_str(), namespace]
+            .iter()
+            .any(|value| value.split(['/', '\\']).any(|segment| segment == ".."));
+        let is_valid = !has_parent
+            && !Path::new(&self.filename).has_root()
+            && path.components().count() > 0
+            && path
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)));
+        if !is_valid {
+            return Err(ExportError::InvalidPath {
+                id: result.metadata.id.clone(),
+                path: rendered,
+            });
         }
+        Ok(path)
+    }
+
+    /// Returns the file extension of the given block: the extension of the
+    /// block language, or the source file extension when the block has no
+    /// language.
+    #[must_use]
+    pub fn extension(&self, result: &ContentResults) -> String {
+        if let Some(lang) = &result.metadata.lang {
+            let lang = lang.to_lowercase();
+            return self
+                .extensions
+                .get(&lang)
+                .cloned()
+                .unwrap_or_else(|| lang_extension(&lang).to_string());
+        }
+        result.location.path.extension().map_or_else(
+            || DEFAULT_EXTENSION.to_string(),
+            |extension| extension.to_string_lossy().to_string(),
+        )
+    }
+
+    /// Write the given blocks to the given folder and save the manifest of
+    /// the written files. Returns the stale files of the previous exports,
+    /// which are removed when [`Layout::prune`] is enabled, and are kept in
+    /// the manifest otherwise.
+    ///
+    /// # Errors
+    /// When a block path is invalid or could not write or remove the files
+    pub fn write(
+        &self,
+        folder: &Path,
+        results: &[ContentResults],
+    ) -> ExportResult<Vec<ManifestFile>> {
+        let previous = Manifest::load(folder)?;
+        let mut manifest = Manifest::default();
+        for result in results {
+            let path = self.path(result)?;
+            let file_path = folder.join(&path);
+            if let Some(parent) = file_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let mut file = File::create(file_path)?;
+            file.write_all(result.data.as_bytes())?;
+
+            let path = path
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            if manifest.files.iter().any(|file| file.path == path) {
+                tracing::warn!(id = result.metadata.id, path, "export file overwritten");
+                manifest.files.retain(|file| file.path != path);
+            }
+            manifest.files.push(ManifestFile {
+                path,
+                id: result.metadata.id.clone(),
+            });
+        }
+
+        let stale = previous
+            .files
+            .into_iter()
+            .filter(|file| !manifest.files.iter().any(|f| f.path == file.path))
+            .collect::<Vec<_>>();
+
+        for file in &stale {
+            if self.prune {
+                remove_stale(folder, &file.path)?;
+            } else {
+                manifest.files.push(file.clone());
+            }
+        }
+
+        manifest.files.sort_by(|a, b| a.path.cmp(&b.path));
+        manifest.save(folder)?;
+        Ok(stale)
+    }
+}
+
+/// Remove the given stale file and the folders that became empty. Paths that
+/// are not inside the output folder are skipped.
+fn remove_stale(folder: &Path, path: &str) -> ExportResult<()> {
+    let relative = Path::new(path);
+    if !relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        tracing::warn!(path, "skip stale file outside of the output folder");
+        return Ok(());
+    }
+
+    let file_path = folder.join(relative);
+    if file_path.is_file() {
+        std::fs::remove_file(&file_path)?;
+    }
+
+    for dir in file_path.ancestors().skip(1) {
+        if dir == folder || std::fs::remove_dir(dir).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+impl Manifest {
+    /// Load the manifest from the given folder. Returns an empty manifest
+    /// when the manifest file not exists.
+    ///
+    /// # Errors
+    /// When could not read or parse the manifest file
+    pub fn load(folder: &Path) -> ExportResult<Self> {
+        let path = folder.join(MANIFEST_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_reader(File::open(path)?)?)
+    }
+
+    /// Save the manifest to the given folder.
+    ///
+    /// # Errors
+    /// When could not write the manifest file
+    pub fn save(&self, folder: &Path) -> ExportResult<()> {
+        let file = File::create(folder.join(MANIFEST_FILE))?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+}
+
+/// Replace the characters that are not allowed in a file name.
+fn sanitize(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| {
+            if INVALID_FILENAME_CHARS.contains(&c) || c.is_control() {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+/// Returns the file extension of the given language name.
+fn lang_extension(lang: &str) -> &str {
+    match lang {
+        "rust" => "rs",
+        "python" => "py",
+        "javascript" => "js",
+        "typescript" => "ts",
+        "ruby" => "rb",
+        "bash" | "shell" | "zsh" => "sh",
+        "markdown" => "md",
+        "yml" => "yaml",
+        "text" | "plain" => DEFAULT_EXTENSION,
+        _ => lang,
     }
 }
 
@@ -56,34 +456,32 @@ impl Output {
 }
 
 impl Content {
-    /// Export content results based on the specified export options.
+    /// Export content results based on the specified export options. Returns
+    /// the stale files of the output folder when only the content is exported
+    /// to a folder (see [`Layout::write`]).
     ///
     /// # Errors
     ///
     /// When have io errors like read/write file or create directory or could
     /// not parse [`ContentResults`] to a given format
-    pub fn export(&self, results: Vec<ContentResults>) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn export(
+        &self,
+        results: Vec<ContentResults>,
+    ) -> Result<Vec<ManifestFile>, Box<dyn std::error::Error>> {
         match self {
-            Self::Only(output) => {
-                for result in results {
-                    match output {
-                        Output::Path(path) => {
-                            let file_path = path.join(result.metadata.id);
-                            if let Some(parent) = file_path.parent() {
-                                std::fs::create_dir_all(parent)?;
-                            }
-                            let mut file = File::create(file_path)?;
-                            file.write_all(result.data.as_bytes())?;
-                        }
-                        Output::Stdout => println!("{}", result.data),
+            Self::Only(output, layout) => match output {
+                Output::Path(path) => {
+                    std::fs::create_dir_all(path)?;
+                    return Ok(layout.write(path, &results)?);
+                }
+                Output::Stdout => {
+                    for result in results {
+                        println!("{}", result.data);
                     }
                 }
-            }
+            },
             Self::All(output, format) => {
-                let content = match format {
-                    Format::Json => serde_json::to_string_pretty(&results)?,
-                    Format::Yaml => serde_yaml::to_string(&results)?,
-                };
+                let content = format.render(&results)?;
                 match output {
                     Output::Path(path) => {
                         let file_path = if path.extension().is_some() {
@@ -102,7 +500,198 @@ impl Content {
                     Output::Stdout => println!("{content}"),
                 }
             }
+            Self::Book(output, layout, style) => {
+                let pages = layout.render(&results, *style)?;
+                match output {
+                    Output::Path(path) => {
+                        for page in pages {
+                            let file_path = path.join(&page.path);
+                            if let Some(parent) = file_path.parent() {
+                                std::fs::create_dir_all(parent)?;
+                            }
+                            let mut file = File::create(file_path)?;
+                            file.write_all(page.content.as_bytes())?;
+                        }
+                    }
+                    Output::Stdout => {
+                        for page in pages {
+                            println!("{}", page.content);
+                        }
+                    }
+                }
+            }
         }
-        Ok(())
+        Ok(vec![])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use insta::assert_snapshot;
+    use tree_fs::Tree;
+
+    use super::*;
+
+    fn content(id: &str, lang: Option<&str>, path: &str) -> ContentResults {
+        let mut content = ContentResults::new_for_test(id, &format!("{id} content"), path, 1, 3);
+        content.metadata.lang = lang.map(ToString::to_string);
+        content.relative_path = path.to_string();
+        content
+    }
+
+    #[test]
+    fn can_render_formats() {
+        let mut with_fence = content("with-fence", Some("markdown"), "README.md");
+        with_fence.data = "Run:\n```sh\nrdocs collect .\n```".to_string();
+        let mut with_quotes = content("with, quotes", None, "src/main.rs");
+        with_quotes.data = "println!(\"hi\");".to_string();
+        let results = vec![
+            content("example", Some("rust"), "src/lib.rs"),
+            with_fence,
+            with_quotes,
+        ];
+
+        assert_snapshot!(Format::Toml.render(&results).unwrap());
+        assert_snapshot!(Format::Ndjson.render(&results).unwrap());
+        assert_snapshot!(Format::Csv.render(&results).unwrap());
+        assert_snapshot!(Format::Markdown.render(&results).unwrap());
+    }
+
+    #[test]
+    fn can_get_layout_path() {
+        let layout = Layout {
+            filename: "{namespace}/{source_stem}/{id}.{ext}".to_string(),
+            extensions: BTreeMap::from([("python".to_string(), "python".to_string())]),
+            ..Layout::default()
+        };
+        let path = |result: &ContentResults| layout.path(result).unwrap();
+
+        assert_eq!(
+            path(&content("example", Some("rust"), "src/lib.rs")),
+            PathBuf::from("lib/example.rs")
+        );
+        assert_eq!(
+            path(&content("example", Some("python"), "src/lib.rs")),
+            PathBuf::from("lib/example.python")
+        );
+        assert_eq!(
+            path(&content("docs/example", None, "src/main.go")),
+            PathBuf::from("main/docs_example.go")
+        );
+
+        let mut namespaced = content("example", None, "src/lib.rs");
+        namespaced.metadata.qualify("my-crate");
+        assert_eq!(
+            path(&namespaced),
+            PathBuf::from("my-crate/lib/my-crate__example.rs")
+        );
+
+        assert_eq!(
+            Layout::default()
+                .path(&content("example", Some("rust"), "src/lib.rs"))
+                .unwrap(),
+            PathBuf::from("example")
+        );
+    }
+
+    #[test]
+    fn reject_invalid_layout_path() {
+        let invalid = |filename: &str, id: &str| {
+            let layout = Layout {
+                filename: filename.to_string(),
+                ..Layout::default()
+            };
+            matches!(
+                layout.path(&content(id, None, "src/lib.rs")),
+                Err(ExportError::InvalidPath { .. })
+            )
+        };
+
+        assert!(invalid("{id}", ".."));
+        assert!(invalid("{id}/file", "."));
+        assert!(invalid("../{id}", "example"));
+        assert!(invalid("/tmp/{id}", "example"));
+        assert!(invalid("{lang}", "example"));
+        assert!(invalid("{id}", "../example"));
+        assert!(invalid("{id}", "docs/../example"));
+        assert!(!invalid("{id}", "docs/example"));
+
+        let mut namespaced = content("example", None, "src/lib.rs");
+        namespaced.metadata.namespace = Some("../docs".to_string());
+        let layout = Layout {
+            filename: "{namespace}/{id}".to_string(),
+            ..Layout::default()
+        };
+        assert!(matches!(
+            layout.path(&namespaced),
+            Err(ExportError::InvalidPath { .. })
+        ));
+    }
+
+    #[test]
+    fn can_write_layout() {
+        let res = Tree::default().add("README.md", "").create().unwrap();
+        let layout = Layout {
+            filename: "{source_stem}/{id}.{ext}".to_string(),
+            ..Layout::default()
+        };
+        // the manifest is sorted by path, whatever the blocks order
+        let results = vec![
+            content("second", Some("sh"), "src/lib.rs"),
+            content("first", Some("rust"), "src/lib.rs"),
+        ];
+
+        assert!(layout.write(&res, &results).unwrap().is_empty());
+        assert_eq!(
+            std::fs::read_to_string(res.join("lib/first.rs")).unwrap(),
+            "first content"
+        );
+        assert_eq!(
+            Manifest::load(&res).unwrap().files,
+            vec![
+                ManifestFile {
+                    path: "lib/first.rs".to_string(),
+                    id: "first".to_string(),
+                },
+                ManifestFile {
+                    path: "lib/second.sh".to_string(),
+                    id: "second".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn can_prune_stale_files() {
+        let res = Tree::default().add("README.md", "").create().unwrap();
+        let mut layout = Layout {
+            filename: "{source_stem}/{id}.{ext}".to_string(),
+            ..Layout::default()
+        };
+        let results = vec![
+            content("first", Some("rust"), "src/lib.rs"),
+            content("second", Some("rust"), "src/main.rs"),
+        ];
+        layout.write(&res, &results).unwrap();
+
+        let stale = layout.write(&res, &results[..1]).unwrap();
+        assert_eq!(
+            stale,
+            vec![ManifestFile {
+                path: "main/second.rs".to_string(),
+                id: "second".to_string(),
+            }]
+        );
+        assert!(res.join("main/second.rs").exists());
+        assert_eq!(Manifest::load(&res).unwrap().files.len(), 2);
+
+        layout.prune = true;
+        assert_eq!(layout.write(&res, &results[..1]).unwrap(), stale);
+        assert!(!res.join("main").exists());
+        assert!(res.join("lib/first.rs").exists());
+        assert_eq!(Manifest::load(&res).unwrap().files.len(), 1);
+
+        assert!(layout.write(&res, &results[..1]).unwrap().is_empty());
     }
 }
@@ -3,10 +3,10 @@
 //! This module provides functionality to parse files and extract content based
 //! on specified patterns.
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, HashMap, HashSet},
     fs::File,
     io::{self, BufRead, Read},
-    path::Path,
+    path::{Path, PathBuf},
 };
 
 use lazy_static::lazy_static;
@@ -15,20 +15,27 @@ use regex::Regex;
 use serde::{Deserialize, Serialize};
 
 lazy_static! {
-    /// Extract the block metadata
-    static ref PARSER_INFO_RE: Regex = Regex::new(r"(?:<id:(.*?)>)").unwrap();
+    /// Extract the block language, for example `<lang:rust>`
+    static ref PARSER_LANG_RE: Regex = Regex::new(r"<lang:\s*([\w\-]+)\s*>").unwrap();
 }
 
 use crate::{
     collect::Collector,
+    command::{self, CacheMode, Command},
     errors::{ParseError, ParserResult},
-    pattern::Pattern,
+    markdown,
+    pattern::{self, Pattern, Preset, Presets},
+    vars::{self, Variables},
 };
 
+/// The separator between the namespace and the block ID.
+pub const NAMESPACE_SEPARATOR: &str = "::";
+
 /// Represents a parser for extracting content from files.
 #[derive(Default)]
 pub struct Parser {
     config: Config,
+    command_cache: CacheMode,
 }
 
 /// Represents configuration for the parser, including patterns to match.
@@ -36,22 +43,92 @@ pub struct Parser {
 pub struct Config {
     /// Patterns used by the parser.
     patterns: Vec<Pattern>,
+    /// The built-in [`Preset`] patterns that are added to the patterns, by
+    /// the file extension. All the presets are enabled in the default
+    /// configuration, and disabled in configuration files unless set.
+    #[serde(default = "no_presets")]
+    presets: Presets,
+    /// Substitute variables in the extracted content (see [`crate::vars`]).
+    /// Disabled when not set.
+    #[serde(default)]
+    variables: bool,
+    /// Variables that are substituted in the extracted content, in addition
+    /// to the built-in variables.
+    #[serde(default)]
+    vars: BTreeMap<String, String>,
+    /// Commands that their output is used as content blocks (see
+    /// [`crate::command`]).
+    #[serde(default)]
+    commands: Vec<Command>,
+    /// Extract Markdown sections as content blocks (see [`crate::markdown`]).
+    /// Disabled when not set.
+    #[serde(default)]
+    markdown: Option<markdown::Config>,
+    /// Assign a generated ID to blocks without an ID, instead of dropping
+    /// them. Disabled when not set.
+    #[serde(default)]
+    anonymous_ids: Option<AnonymousIds>,
+    /// Prefix the block IDs with the namespace of the source file, for
+    /// example `my-crate::quick-start`. Disabled when not set.
+    #[serde(default)]
+    namespace: Option<Namespace>,
+}
+
+/// Enum representing how the IDs of anonymous blocks are generated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AnonymousIds {
+    /// The file path relative to the collect folder and the start line, for
+    /// example `src/lib.rs:3`.
+    Location,
+    /// The file stem and the block number in the file, for example `lib-1`.
+    Stem,
+}
+
+/// Enum representing the namespace of the block IDs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Namespace {
+    /// The package name of the crate that contains the source file.
+    Crate,
+    /// The source file directory, relative to the collect folder.
+    Directory,
+}
+
+const fn no_presets() -> Presets {
+    Presets::All(false)
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             patterns: vec![Pattern::default()],
+            presets: Presets::All(true),
+            variables: false,
+            vars: BTreeMap::new(),
+            commands: vec![],
+            markdown: None,
+            anonymous_ids: None,
+            namespace: None,
         }
     }
 }
 
+/// Represents the extracted blocks and the commands that could not run.
+#[derive(Debug)]
+pub struct Extraction {
+    pub blocks: Vec<ContentResults>,
+    pub command_failures: Vec<command::Failure>,
+}
+
 /// Represents content associated with a file path and a list of patterns.
 #[derive(Debug)]
 pub struct Content<'a> {
     path: &'a Path,
     patterns: &'a Vec<Pattern>,
     pub expected_capture_count: usize,
+    /// The anonymous IDs style and the file path used in the generated IDs.
+    anonymous_ids: Option<(AnonymousIds, PathBuf)>,
 }
 
 /// Represents a block of content with metadata and lines.
@@ -59,20 +136,118 @@ pub struct Content<'a> {
 pub struct ContentBlock {
     pub metadata: ContentMetadata,
     pub lines: Vec<String>,
+    pub start_line: usize,
+}
+
+/// Represents the location of a content block in the source file.
+///
+/// Lines are 1-based and inclusive, and point to the start and end pattern
+/// lines, or to the heading and the last line of a Markdown section.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Location {
+    pub path: PathBuf,
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+impl Location {
+    /// Returns the path relative to `base` with `/` separators. The path is
+    /// returned as is when it is not in `base`.
+    #[must_use]
+    pub fn relative_path(&self, base: &Path) -> String {
+        self.path
+            .strip_prefix(base)
+            .unwrap_or(&self.path)
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Returns `true` when the block is generated, for example from a command
+    /// output, and has no source lines.
+    #[must_use]
+    pub const fn is_generated(&self) -> bool {
+        self.start_line == 0
+    }
 }
 
 /// Represents metadata associated with content, including an ID.
 #[derive(Debug, Clone, Serialize)]
 pub struct ContentMetadata {
     pub id: String,
+    /// The block language, set with the `<lang:[LANG]>` tag.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
+    /// The block attributes, captured by the named groups of the pattern
+    /// metadata regex.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub attributes: BTreeMap<String, String>,
+    /// The namespace of the block ID, set when namespaced IDs are enabled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
 }
 
 /// Represents the final results after extracting content, including metadata
 /// and cleaned-up data.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ContentResults {
     pub metadata: ContentMetadata,
     pub data: String,
+    /// The source path relative to the collect folder, with `/` separators.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub relative_path: String,
+    #[serde(skip)]
+    pub location: Location,
+}
+
+impl Config {
+    /// Returns the patterns used by the parser.
+    #[must_use]
+    pub fn patterns(&self) -> &[Pattern] {
+        &self.patterns
+    }
+
+    /// Returns the patterns that apply to the given file path, relative to
+    /// the collect folder: the configured patterns, the preset pattern of the
+    /// file and the Markdown pattern when enabled. Patterns with the same
+    /// start regex are added once.
+    #[must_use]
+    pub fn file_patterns(&self, path: &Path) -> Vec<Pattern> {
+        let mut patterns = vec![];
+        for pattern in self.all_patterns() {
+            if pattern.applies_to(path) {
+                push_unique(&mut patterns, pattern);
+            }
+        }
+        patterns
+    }
+
+    /// Returns the patterns of all the files: the configured patterns, all
+    /// the preset patterns and the Markdown pattern when enabled.
+    #[must_use]
+    pub fn all_patterns(&self) -> Vec<Pattern> {
+        let mut patterns = self.patterns.clone();
+        patterns.extend(self.presets.enabled().iter().map(Preset::pattern));
+        if let Some(markdown) = &self.markdown {
+            patterns.push(Pattern {
+                extensions: markdown.extensions.clone(),
+                paths: vec![],
+                ..Preset::Html.pattern()
+            });
+        }
+        patterns
+    }
+}
+
+/// Adds the given pattern when there is no pattern with the same start regex.
+fn push_unique(patterns: &mut Vec<Pattern>, pattern: Pattern) {
+    if !patterns
+        .iter()
+        .any(|p| p.start.as_str() == pattern.start.as_str())
+    {
+        patterns.push(pattern);
+    }
 }
 
 impl Parser {
@@ -81,69 +256,393 @@ impl Parser {
     pub fn new() -> Self {
         Self {
             config: Config::default(),
+            command_cache: CacheMode::Off,
         }
     }
 
     /// Creates a new instance of [`Parser`] with the specified configuration.
     #[must_use]
     pub const fn with_config(config: Config) -> Self {
-        Self { config }
+        Self {
+            config,
+            command_cache: CacheMode::Off,
+        }
+    }
+
+    /// Sets how the recorded command outputs are used.
+    #[must_use]
+    pub const fn with_command_cache(mut self, command_cache: CacheMode) -> Self {
+        self.command_cache = command_cache;
+        self
     }
 
     /// Extracts content from files collected by the provided collector.
+    /// Commands that could not run are logged and skipped, see
+    /// [`Parser::extract`] to get them.
     #[must_use]
     pub fn extract_content(&self, collector: &Collector) -> Vec<ContentResults> {
+        self.extract(collector).blocks
+    }
+
+    /// Extracts content from files collected by the provided collector, and
+    /// returns the commands that could not run.
+    #[must_use]
+    pub fn extract(&self, collector: &Collector) -> Extraction {
         let files = collector.collect_files();
-        files
+        let variables = self
+            .config
+            .variables
+            .then(|| Variables::new(self.config.vars.clone()));
+
+        let mut results = files
             .par_iter()
             .flat_map(|path| {
                 let span =
                     tracing::span!(tracing::Level::TRACE, "collect_file", path = %path.display());
                 let _guard = span.enter();
 
-                let parse_content = match Content::new(path.as_path(), &self.config.patterns) {
-                    Ok(parse_content) => parse_content,
-                    Err(err) => {
-                        tracing::error!(err = %err, "could not parse file content");
-                        return None;
-                    }
-                };
+                let markdown = self
+                    .config
+                    .markdown
+                    .as_ref()
+                    .filter(|markdown| markdown.is_markdown(path));
 
-                if parse_content.expected_capture_count == 0 {
-                    tracing::trace!("captures not found in file");
-                    return None;
-                }
+                let mut results =
+                    markdown.map_or_else(Vec::new, |markdown| markdown.extract_sections(path));
+                results.extend(self.extract_file(path, &collector.folder));
 
-                match parse_content.extract() {
-                    Ok(res) => Some(res),
-                    Err(err) => {
-                        tracing::error!(err = %err,"could not parse file content");
-                        None
-                    }
+                let namespace = self
+                    .config
+                    .namespace
+                    .and_then(|namespace| namespace.of(path, &collector.folder));
+
+                results
+                    .into_iter()
+                    .map(|mut result| {
+                        if let Some(namespace) = &namespace {
+                            result.metadata.qualify(namespace);
+                        }
+                        if let Some(variables) = &variables {
+                            result.data = variables.substitute(&result.data, path);
+                        }
+                        result
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let (outputs, command_failures) =
+            command::collect(&self.config.commands, &collector.folder, self.command_cache);
+        results.extend(outputs);
+        for result in &mut results {
+            result.relative_path = result.location.relative_path(&collector.folder);
+        }
+
+        Extraction {
+            blocks: self.resolve_includes(results),
+            command_failures,
+        }
+    }
+
+    /// Extracts the content blocks of the given file.
+    fn extract_file(&self, path: &Path, folder: &Path) -> Vec<ContentResults> {
+        let patterns = self
+            .config
+            .file_patterns(path.strip_prefix(folder).unwrap_or(path));
+        let parse_content = match Content::new(path, &patterns) {
+            Ok(parse_content) => parse_content,
+            Err(err) => {
+                tracing::error!(err = %err, "could not parse file content");
+                return vec![];
+            }
+        };
+
+        if parse_content.expected_capture_count == 0 {
+            tracing::trace!("captures not found in file");
+            return vec![];
+        }
+
+        let parse_content = match self.config.anonymous_ids {
+            Some(anonymous_ids) => parse_content
+                .with_anonymous_ids(anonymous_ids, path.strip_prefix(folder).unwrap_or(path)),
+            None => parse_content,
+        };
+
+        match parse_content.extract() {
+            Ok(res) => res,
+            Err(err) => {
+                tracing::error!(err = %err,"could not parse file content");
+                vec![]
+            }
+        }
+    }
+
+    /// Replaces the include lines of the given results with the content of the
+    /// included blocks. Blocks with a missing include or with an include cycle
+    /// are dropped from the results.
+    #[must_use]
+    pub fn resolve_includes(&self, results: Vec<ContentResults>) -> Vec<ContentResults> {
+        let patterns = self.config.all_patterns();
+        let blocks = results
+            .iter()
+            .rev()
+            .flat_map(|result| {
+                result
+                    .metadata
+                    .reference_ids(&results)
+                    .into_iter()
+                    .map(move |id| (id, result))
+            })
+            .collect::<HashMap<_, _>>();
+
+        let resolved = results
+            .iter()
+            .map(|result| {
+                let mut stack = vec![result.metadata.id.clone()];
+                Self::resolve_block(result, &patterns, &blocks, &mut stack)
+            })
+            .collect::<Vec<_>>();
+
+        results
+            .into_iter()
+            .zip(resolved)
+            .filter_map(|(mut result, data)| match data {
+                Ok(data) => {
+                    result.data = data;
+                    Some(result)
+                }
+                Err(err) => {
+                    tracing::error!(err = %err, id = result.metadata.id, "could not resolve includes");
+                    None
                 }
             })
-            .flatten()
-            .collect::<Vec<_>>()
+            .collect()
+    }
+
+    /// Resolve the include lines of the given block recursively. An
+    /// include ID is resolved in the namespace of the block first.
+    fn resolve_block(
+        block: &ContentResults,
+        patterns: &[Pattern],
+        blocks: &HashMap<&str, &ContentResults>,
+        stack: &mut Vec<String>,
+    ) -> ParserResult<String> {
+        let mut lines = vec![];
+        for line in block.data.lines() {
+            let Some(pattern) = patterns.iter().find(|p| p.include_with(line)) else {
+                lines.push(line.to_string());
+                continue;
+            };
+
+            let Some(metadata) = ContentMetadata::with_regex(line, &pattern.metadata) else {
+                tracing::warn!(
+                    line_content = line,
+                    "include line has invalid format. invalid <id:[ID]>"
+                );
+                lines.push(line.to_string());
+                continue;
+            };
+
+            let included = block
+                .metadata
+                .namespace
+                .as_ref()
+                .and_then(|namespace| {
+                    blocks.get(format!("{namespace}{NAMESPACE_SEPARATOR}{}", metadata.id).as_str())
+                })
+                .or_else(|| blocks.get(metadata.id.as_str()))
+                .ok_or_else(|| ParseError::IncludeNotFound {
+                    id: metadata.id.clone(),
+                })?;
+
+            if stack.contains(&included.metadata.id) {
+                stack.push(included.metadata.id.clone());
+                return Err(ParseError::IncludeCycle {
+                    ids: stack.join(" -> "),
+                });
+            }
+
+            stack.push(included.metadata.id.clone());
+            let included = Self::resolve_block(included, patterns, blocks, stack)?;
+            stack.pop();
+
+            let indent = &line[..line.len() - line.trim_start().len()];
+            lines.extend(included.lines().map(|included_line| {
+                if included_line.is_empty() {
+                    String::new()
+                } else {
+                    format!("{indent}{included_line}")
+                }
+            }));
+        }
+
+        Ok(lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+impl ContentResults {
+    /// Creates a block with the given ID and data, without language and
+    /// attributes, located at the given source lines.
+    pub(crate) fn new_for_test(
+        id: &str,
+        data: &str,
+        path: impl Into<PathBuf>,
+        start_line: usize,
+        end_line: usize,
+    ) -> Self {
+        Self {
+            metadata: ContentMetadata {
+                id: id.to_string(),
+                lang: None,
+                attributes: BTreeMap::new(),
+                namespace: None,
+            },
+            data: data.to_string(),
+            relative_path: String::new(),
+            location: Location {
+                path: path.into(),
+                start_line,
+                end_line,
+            },
+        }
     }
 }
 
 impl ContentMetadata {
-    /// Creates a new instance of [`ContentMetadata`] from the specified string.
+    /// Returns the block ID without the namespace.
+    #[must_use]
+    pub fn short_id(&self) -> &str {
+        self.namespace
+            .as_ref()
+            .and_then(|namespace| {
+                self.id
+                    .strip_prefix(namespace.as_str())?
+                    .strip_prefix(NAMESPACE_SEPARATOR)
+            })
+            .unwrap_or(&self.id)
+    }
+
+    /// Returns the IDs that target markers can use to reference the block:
+    /// the block ID, and the short ID when no other block of the given
+    /// blocks has the same short ID.
+    #[must_use]
+    pub fn reference_ids<'b>(&'b self, blocks: &[ContentResults]) -> Vec<&'b str> {
+        let short_id = self.short_id();
+        if short_id == self.id
+            || blocks
+                .iter()
+                .filter(|block| block.metadata.short_id() == short_id)
+                .count()
+                > 1
+        {
+            return vec![self.id.as_str()];
+        }
+        vec![self.id.as_str(), short_id]
+    }
+
+    /// Prefix the block ID with the given namespace.
+    pub fn qualify(&mut self, namespace: &str) {
+        self.id = format!("{namespace}{NAMESPACE_SEPARATOR}{}", self.id);
+        self.namespace = Some(namespace.to_string());
+    }
+
+    /// Creates a new instance of [`ContentMetadata`] from the specified string,
+    /// with the default metadata regex.
     #[must_use]
     pub fn new(str: &str) -> Option<Self> {
-        let captures = PARSER_INFO_RE.captures(str)?;
+        Self::with_regex(str, &pattern::default_metadata())
+    }
+
+    /// Creates a new instance of [`ContentMetadata`] from the specified string,
+    /// with the given metadata regex. Returns `None` when the regex does not
+    /// match or the `id` named group is empty.
+    #[must_use]
+    pub fn with_regex(str: &str, regex: &Regex) -> Option<Self> {
+        let captures = regex.captures(str)?;
+        let Some(id) = captures
+            .name("id")
+            .map(|m| m.as_str().trim())
+            .filter(|id| !id.is_empty())
+        else {
+            tracing::info!("id not found");
+            return None;
+        };
+
+        let lang = captures
+            .name("lang")
+            .map_or_else(|| lang_tag(str), |m| Some(m.as_str().trim().to_string()));
+
+        let attributes = regex
+            .capture_names()
+            .flatten()
+            .filter(|name| !matches!(*name, "id" | "lang"))
+            .filter_map(|name| {
+                let value = captures.name(name)?;
+                Some((name.to_string(), value.as_str().trim().to_string()))
+            })
+            .collect();
+
         Some(Self {
-            id: captures.get(1).map_or_else(
-                || {
-                    tracing::info!("id not found");
-                    None
-                },
-                |m| Some(m.as_str().trim().to_string()),
-            )?,
+            id: id.to_string(),
+            lang,
+            attributes,
+            namespace: None,
         })
     }
 }
 
+/// Returns the block language of the `<lang:[LANG]>` tag.
+fn lang_tag(str: &str) -> Option<String> {
+    PARSER_LANG_RE
+        .captures(str)
+        .and_then(|captures| captures.get(1))
+        .map(|m| m.as_str().to_string())
+}
+
+impl AnonymousIds {
+    /// Returns the generated ID of an anonymous block. `path` is the file
+    /// path relative to the collect folder, `line` is the 1-based start line
+    /// and `number` is the 1-based number of the anonymous block in the file.
+    #[must_use]
+    pub fn id(self, path: &Path, line: usize, number: usize) -> String {
+        match self {
+            Self::Location => format!(
+                "{}:{line}",
+                path.components()
+                    .map(|component| component.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/")
+            ),
+            Self::Stem => format!(
+                "{}-{number}",
+                path.file_stem()
+                    .map_or_else(|| "block".into(), |stem| stem.to_string_lossy())
+            ),
+        }
+    }
+}
+
+impl Namespace {
+    /// Returns the namespace of the given source file. Files outside of a
+    /// crate, or in the collect folder itself, have no namespace.
+    #[must_use]
+    pub fn of(self, path: &Path, folder: &Path) -> Option<String> {
+        match self {
+            Self::Crate => vars::package_name(&vars::find_manifest(path)?),
+            Self::Directory => {
+                let dir = path.parent()?.strip_prefix(folder).ok()?;
+                let namespace = dir
+                    .components()
+                    .map(|component| component.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                (!namespace.is_empty()).then_some(namespace)
+            }
+        }
+    }
+}
+
 impl<'a> Content<'a> {
     /// Creates a new instance of [`Content`].
     ///
@@ -173,9 +672,18 @@ impl<'a> Content<'a> {
             path,
             patterns,
             expected_capture_count,
+            anonymous_ids: None,
         })
     }
 
+    /// Assign a generated ID to blocks without an ID, instead of dropping
+    /// them. `path` is the file path used in the generated IDs.
+    #[must_use]
+    pub fn with_anonymous_ids(mut self, anonymous_ids: AnonymousIds, path: &Path) -> Self {
+        self.anonymous_ids = Some((anonymous_ids, path.to_path_buf()));
+        self
+    }
+
     /// Extracts content based on the defined patterns.
     ///
     /// # Errors
@@ -186,8 +694,11 @@ impl<'a> Content<'a> {
         let reader = io::BufReader::new(file);
 
         let mut level_stack = HashMap::new();
+        let mut hidden = HashSet::new();
+        let mut elided = HashSet::new();
 
         let mut collected_scoped_content = BTreeMap::new();
+        let mut anonymous_count = 0;
 
         // let mut current_levels = vec![0; self.patterns.len()];
         // let mut max_level = 0;
@@ -197,7 +708,18 @@ impl<'a> Content<'a> {
 
             for (pattern_index, pattern) in self.patterns.iter().enumerate() {
                 if pattern.start_with(&line) {
-                    let Some(metadata) = ContentMetadata::new(&line) else {
+                    let metadata =
+                        ContentMetadata::with_regex(&line, &pattern.metadata).or_else(|| {
+                            let (anonymous_ids, path) = self.anonymous_ids.as_ref()?;
+                            anonymous_count += 1;
+                            Some(ContentMetadata {
+                                id: anonymous_ids.id(path, line_index + 1, anonymous_count),
+                                lang: lang_tag(&line),
+                                attributes: BTreeMap::new(),
+                                namespace: None,
+                            })
+                        });
+                    let Some(metadata) = metadata else {
                         tracing::warn!(
                             line_content = line,
                             line_index,
@@ -209,6 +731,7 @@ impl<'a> Content<'a> {
                     let content_block = ContentBlock {
                         metadata,
                         lines: vec![],
+                        start_line: line_index + 1,
                     };
                     level_stack
                         .entry(pattern_index)
@@ -220,22 +743,56 @@ impl<'a> Content<'a> {
                             collected_scoped_content
                                 .entry(pattern_index)
                                 .or_insert_with(Vec::new)
-                                .push(last.clone());
+                                .push((last.clone(), line_index + 1));
                         }
                         level.pop();
-                    };
+                    }
                     // level_stack.remove(&pattern_index);
-                } else if let Some(levels) = level_stack.get_mut(&pattern_index) {
-                    for level in levels {
-                        level.lines.push(line.clone());
+                } else if pattern.hide_with(&line) {
+                    hidden.insert(pattern_index);
+                } else if pattern.show_with(&line) {
+                    hidden.remove(&pattern_index);
+                } else if pattern.elide_with(&line) {
+                    if elided.insert(pattern_index) {
+                        if let Some(levels) = level_stack.get_mut(&pattern_index) {
+                            for level in levels {
+                                level.lines.push(pattern.elide_placeholder(&line));
+                            }
+                        }
+                    }
+                } else if pattern.end_elide_with(&line) {
+                    elided.remove(&pattern_index);
+                } else if !hidden.contains(&pattern_index)
+                    && !elided.contains(&pattern_index)
+                    && !pattern.hide_line_with(&line)
+                {
+                    if let Some(levels) = level_stack.get_mut(&pattern_index) {
+                        for level in levels {
+                            level.lines.push(line.clone());
+                        }
                     }
                 }
             }
         }
 
+        Ok(self.content_results(collected_scoped_content))
+    }
+
+    /// Converts the collected blocks of each pattern to content results.
+    fn content_results(
+        &self,
+        collected_scoped_content: BTreeMap<usize, Vec<(ContentBlock, usize)>>,
+    ) -> Vec<ContentResults> {
         let mut results = vec![];
+        let mut locations = HashSet::new();
         for (pattern_index, blocks) in collected_scoped_content {
-            for block in blocks {
+            for (block, end_line) in blocks {
+                // a configured pattern and its preset can extract the same
+                // block. The block of the first pattern is kept.
+                if !locations.insert((block.start_line, end_line)) {
+                    tracing::trace!(id = block.metadata.id, "skip duplicate block");
+                    continue;
+                }
                 let match_content = block.lines.join("\n");
                 let cleanup_result = if let Some(pattern) = self.patterns.get(pattern_index) {
                     pattern.cleanup(&match_content)
@@ -247,11 +804,17 @@ impl<'a> Content<'a> {
                 results.push(ContentResults {
                     metadata: block.metadata,
                     data: cleanup_result.trim().to_string(),
+                    relative_path: String::new(),
+                    location: Location {
+                        path: self.path.to_path_buf(),
+                        start_line: block.start_line,
+                        end_line,
+                    },
                 });
             }
         }
 
-        Ok(results)
+        results
     }
 }
 
@@ -271,12 +834,14 @@ mod tests {
                 end: Regex::new(r".*#END").unwrap(),
                 #[allow(clippy::trivial_regex)]
                 cleanups: vec![Regex::new(r"$").unwrap()],
+                ..Pattern::default()
             },
             Pattern {
                 start: Regex::new(r".*#PATTERN_2_START").unwrap(),
                 end: Regex::new(r".*#PATTERN_2_END").unwrap(),
                 #[allow(clippy::trivial_regex)]
                 cleanups: vec![Regex::new(r"//!").unwrap()],
+                ..Pattern::default()
             },
         ]
     }
@@ -359,12 +924,478 @@ mod tests {
         let binding = res.join("test.rs");
         let c = Content::new(binding.as_path(), &patterns).unwrap();
 
-        assert_debug_snapshot!(c.extract());
+        with_settings!({
+            filters => vec![
+                ("path: .*","path: REDUCT")
+            ]
+        }, {
+            assert_debug_snapshot!(c.extract());
+        });
+    }
+
+    #[test]
+    fn can_resolve_includes() {
+        let setup = r"// 📖 #START <id:setup>
+let config = Config::default();
+// 📖 #END
+";
+        let example = r"// 📖 #START <id:full-example>
+fn main() {
+    // 📖 #INCLUDE <id:setup>
+    run(config);
+}
+// 📖 #END
+";
+        let res = Tree::default()
+            .add("setup.rs", setup)
+            .add("example.rs", example)
+            .create()
+            .unwrap();
+        let collector = Collector::new(&res).unwrap();
+
+        let mut results = Parser::new().extract_content(&collector);
+        results.sort_by(|a, b| a.metadata.id.cmp(&b.metadata.id));
+        let results = results
+            .into_iter()
+            .map(|result| (result.metadata.id, result.data))
+            .collect::<Vec<_>>();
+        assert_debug_snapshot!(results);
+    }
+
+    #[test]
+    fn can_extract_with_presets() {
+        let res = Tree::default()
+            .add(
+                "setup.py",
+                "# 📖 #START <id:python>\nimport rdocs\nrdocs.run()  # 📖 hide\n# 📖 #END\n",
+            )
+            .add(
+                "query.sql",
+                "-- 📖 #START <id:sql>\nSELECT 1;\n-- 📖 #END\n",
+            )
+            .add(
+                "index.html",
+                "<!-- 📖 #START <id:html> -->\n<p>rdocs</p>\n<!-- 📖 #END -->\n",
+            )
+            .add(
+                "core.clj",
+                ";; 📖 #START <id:lisp>\n(println \"rdocs\")\n;; 📖 #END\n",
+            )
+            .add(
+                "app.ts",
+                "// 📖 #START <id:ts>\n// 📖 #INCLUDE <id:python>\nconst a = 1;\n// 📖 #END\n",
+            )
+            .add("notes.txt", "# 📖 #START <id:ignored>\ntext\n# 📖 #END\n")
+            .create()
+            .unwrap();
+        let collector = Collector::new(&res).unwrap();
+
+        let mut results = Parser::new().extract_content(&collector);
+        results.sort_by(|a, b| a.metadata.id.cmp(&b.metadata.id));
+        let results = results
+            .into_iter()
+            .map(|result| (result.metadata.id, result.data))
+            .collect::<Vec<_>>();
+        assert_debug_snapshot!(results);
+    }
+
+    #[test]
+    fn can_extract_with_pattern_filters() {
+        let config: Config = serde_yaml::from_str(
+            r##"
+patterns:
+- start: '#\s*📖\s*#START'
+  end: '#\s*📖\s*#END'
+  cleanups: []
+  extensions: [py]
+"##,
+        )
+        .unwrap();
+        let content = "# 📖 #START <id:example>\nprint(1)\n# 📖 #END\n";
+        let res = Tree::default()
+            .add("example.py", content)
+            .add("README.md", content)
+            .create()
+            .unwrap();
+        let collector = Collector::new(&res).unwrap();
+
+        let results = Parser::with_config(config).extract_content(&collector);
+        assert_eq!(results.len(), 1);
+        assert!(results[0].location.path.ends_with("example.py"));
+    }
+
+    #[test]
+    fn can_extract_with_path_filters() {
+        let config: Config = serde_yaml::from_str(
+            r##"
+patterns:
+- start: '#\s*📖\s*#START'
+  end: '#\s*📖\s*#END'
+  cleanups: []
+  paths: ['^scripts/']
+presets: false
+"##,
+        )
+        .unwrap();
+        let content = "# 📖 #START <id:install>\ncargo install rdocs\n# 📖 #END\n";
+        let res = Tree::default()
+            .add("scripts/install", content)
+            .add("docs/scripts/install", content)
+            .create()
+            .unwrap();
+        let collector = Collector::new(&res).unwrap();
+
+        let results = Parser::with_config(config).extract_content(&collector);
+        assert_eq!(results.len(), 1);
+        assert!(results[0].location.path.ends_with("scripts/install"));
+        assert!(!results[0].location.path.ends_with("docs/scripts/install"));
+        assert_eq!(results[0].relative_path, "scripts/install");
+    }
+
+    #[test]
+    fn can_select_presets() {
+        let res = Tree::default()
+            .add(
+                "setup.py",
+                "# 📖 #START <id:python>\nimport rdocs\n# 📖 #END\n",
+            )
+            .add(
+                "query.sql",
+                "-- 📖 #START <id:sql>\nSELECT 1;\n-- 📖 #END\n",
+            )
+            .create()
+            .unwrap();
+        let collector = Collector::new(&res).unwrap();
+
+        let extract = |yaml: &str| {
+            let config: Config = serde_yaml::from_str(yaml).unwrap();
+            let mut ids = Parser::with_config(config)
+                .extract_content(&collector)
+                .into_iter()
+                .map(|result| result.metadata.id)
+                .collect::<Vec<_>>();
+            ids.sort();
+            ids
+        };
+
+        assert!(extract("patterns: []").is_empty());
+        assert_eq!(
+            extract("patterns: []\npresets: true"),
+            vec!["python", "sql"]
+        );
+        assert_eq!(extract("patterns: []\npresets: [sql]"), vec!["sql"]);
+    }
+
+    #[test]
+    fn extract_once_with_preset_markers() {
+        let config: Config = serde_yaml::from_str(
+            r#"
+patterns:
+- start: "// 📖 #START"
+  end: "// 📖 #END"
+  cleanups: []
+presets: true
+"#,
+        )
+        .unwrap();
+        let res = Tree::default()
+            .add(
+                "lib.rs",
+                "// 📖 #START <id:sum>\nlet sum = 1 + 2;\n// 📖 #END\n",
+            )
+            .create()
+            .unwrap();
+        let collector = Collector::new(&res).unwrap();
+
+        let results = Parser::with_config(config).extract_content(&collector);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].metadata.id, "sum");
+    }
+
+    #[test]
+    fn can_extract_with_custom_metadata() {
+        let config: Config = serde_yaml::from_str(
+            r##"
+patterns:
+- start: '<!--\s*snippet\s+start'
+  end: '<!--\s*snippet\s+end'
+  include: '<!--\s*snippet\s+include'
+  metadata: 'id=(?P<id>[\w-]+)(?:\s+lang=(?P<lang>\w+))?(?:\s+title="(?P<title>[^"]*)")?'
+  cleanups: []
+"##,
+        )
+        .unwrap();
+        let content = r#"<!-- snippet start id=install lang=sh title="Install rdocs" -->
+cargo install rdocs
+<!-- snippet end -->
+<!-- snippet start id=usage -->
+<!-- snippet include id=install -->
+rdocs collect .
+<!-- snippet end -->
+"#;
+        let res = Tree::default().add("docs.html", content).create().unwrap();
+        let collector = Collector::new(&res).unwrap();
+
+        let results = Parser::with_config(config).extract_content(&collector);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].metadata.id, "install");
+        assert_eq!(results[0].metadata.lang.as_deref(), Some("sh"));
+        assert_eq!(
+            results[0].metadata.attributes,
+            BTreeMap::from([("title".to_string(), "Install rdocs".to_string())])
+        );
+        assert_eq!(results[1].metadata.id, "usage");
+        assert!(results[1].metadata.attributes.is_empty());
+        assert_eq!(results[1].data, "cargo install rdocs\nrdocs collect .");
+    }
+
+    #[test]
+    fn can_extract_anonymous_blocks() {
+        let content = r"// 📖 #START
+first
+// 📖 #END
+// 📖 #START <id:named>
+named
+// 📖 #END
+// 📖 #START <lang:rust>
+second
+// 📖 #END
+";
+        let res = Tree::default().add("src/lib.rs", content).create().unwrap();
+        let collector = Collector::new(&res).unwrap();
+
+        assert_eq!(Parser::new().extract_content(&collector).len(), 1);
+
+        for (anonymous_ids, expected) in [
+            (
+                AnonymousIds::Location,
+                ["src/lib.rs:1", "named", "src/lib.rs:7"],
+            ),
+            (AnonymousIds::Stem, ["lib-1", "named", "lib-2"]),
+        ] {
+            let config = Config {
+                anonymous_ids: Some(anonymous_ids),
+                ..Config::default()
+            };
+            let results = Parser::with_config(config).extract_content(&collector);
+            assert_eq!(
+                results
+                    .iter()
+                    .map(|result| result.metadata.id.as_str())
+                    .collect::<Vec<_>>(),
+                expected
+            );
+            assert_eq!(results[2].metadata.lang.as_deref(), Some("rust"));
+        }
+    }
+
+    #[test]
+    fn can_extract_with_namespace() {
+        let content = r"// 📖 #START <id:quick-start>
+// 📖 #INCLUDE <id:install>
+// 📖 #END
+// 📖 #START <id:install>
+cargo install
+// 📖 #END
+";
+        let res = Tree::default()
+            .add("first/src/lib.rs", content)
+            .add(
+                "first/Cargo.toml",
+                "[package]\nname = \"first-crate\"\nversion = \"0.1.0\"\n",
+            )
+            .add(
+                "second/lib.rs",
+                &content.replace("cargo install", "cargo add"),
+            )
+            .add("lib.rs", "// 📖 #START <id:root>\nroot\n// 📖 #END\n")
+            .create()
+            .unwrap();
+        let collector = Collector::new(&res).unwrap();
+
+        for (namespace, expected) in [
+            (
+                Namespace::Crate,
+                vec![
+                    ("first-crate::quick-start", "cargo install"),
+                    ("first-crate::install", "cargo install"),
+                    ("quick-start", "cargo add"),
+                    ("install", "cargo add"),
+                    ("root", "root"),
+                ],
+            ),
+            (
+                Namespace::Directory,
+                vec![
+                    ("first/src::quick-start", "cargo install"),
+                    ("first/src::install", "cargo install"),
+                    ("second::quick-start", "cargo add"),
+                    ("second::install", "cargo add"),
+                    ("root", "root"),
+                ],
+            ),
+        ] {
+            let config = Config {
+                namespace: Some(namespace),
+                ..Config::default()
+            };
+            let mut results = Parser::with_config(config)
+                .extract_content(&collector)
+                .into_iter()
+                .map(|result| (result.metadata.id, result.data))
+                .collect::<Vec<_>>();
+            results.sort_by_key(|(id, _)| expected.iter().position(|(expected, _)| expected == id));
+            assert_eq!(
+                results,
+                expected
+                    .iter()
+                    .map(|(id, data)| ((*id).to_string(), (*data).to_string()))
+                    .collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn can_extract_with_variables() {
+        let res = Tree::default()
+            .add(
+                "lib.rs",
+                "// 📖 #START <id:install>\ncargo add rdocs@{{ crate.version }} {{ channel }}\n// 📖 #END\n",
+            )
+            .add(
+                "Cargo.toml",
+                "[package]\nname = \"rdocs\"\nversion = \"0.1.2\"\n",
+            )
+            .create()
+            .unwrap();
+        let collector = Collector::new(&res).unwrap();
+
+        let results = Parser::with_config(Config::default()).extract_content(&collector);
+        assert_eq!(
+            results[0].data,
+            "cargo add rdocs@{{ crate.version }} {{ channel }}"
+        );
+
+        let config = Config {
+            variables: true,
+            vars: BTreeMap::from([("channel".to_string(), "stable".to_string())]),
+            ..Config::default()
+        };
+        let results = Parser::with_config(config).extract_content(&collector);
+        assert_eq!(results[0].data, "cargo add rdocs@0.1.2 stable");
+    }
+
+    #[test]
+    fn can_get_reference_ids() {
+        let content = |namespace: &str, id: &str| {
+            let mut content = ContentResults::new_for_test(id, "", "", 0, 0);
+            if !namespace.is_empty() {
+                content.metadata.qualify(namespace);
+            }
+            content
+        };
+        let blocks = vec![
+            content("first", "quick-start"),
+            content("second", "quick-start"),
+            content("first", "install"),
+            content("", "usage"),
+        ];
+
+        assert_eq!(blocks[0].metadata.short_id(), "quick-start");
+        assert_eq!(
+            blocks[0].metadata.reference_ids(&blocks),
+            vec!["first::quick-start"]
+        );
+        assert_eq!(
+            blocks[2].metadata.reference_ids(&blocks),
+            vec!["first::install", "install"]
+        );
+        assert_eq!(blocks[3].metadata.reference_ids(&blocks), vec!["usage"]);
+    }
+
+    #[test]
+    fn include_cycle() {
+        let content = r"// 📖 #START <id:first>
+// 📖 #INCLUDE <id:second>
+// 📖 #END
+// 📖 #START <id:second>
+// 📖 #INCLUDE <id:first>
+// 📖 #END
+// 📖 #START <id:third>
+third
+// 📖 #END
+";
+        let res = Tree::default().add("test.rs", content).create().unwrap();
+        let collector = Collector::new(&res).unwrap();
+
+        let results = Parser::new().extract_content(&collector);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].metadata.id, "third");
+    }
+
+    #[test]
+    fn can_hide_lines() {
+        let content = r#"// 📖 #START <id:example>
+fn main() {
+    // 📖 #HIDE
+    let config = Config::default();
+    // 📖 #START <id:nested>
+    let result = run(config);
+    // 📖 #SHOW
+    println!("{result}");
+    // 📖 #END
+    std::fs::write("out", result).unwrap(); // 📖 hide
+}
+// 📖 #END
+"#;
+        let res = Tree::default().add("test.rs", content).create().unwrap();
+        let patterns = vec![Pattern::default()];
+        let binding = res.join("test.rs");
+        let c = Content::new(binding.as_path(), &patterns).unwrap();
+
+        let results = c
+            .extract()
+            .unwrap()
+            .into_iter()
+            .map(|result| (result.metadata.id, result.data))
+            .collect::<Vec<_>>();
+        assert_debug_snapshot!(results);
+    }
+
+    #[test]
+    fn can_elide_lines() {
+        let content = r"// 📖 #START <id:example>
+fn main() {
+    let config = Config::default();
+    // 📖 #ELIDE
+    let server = Server::new(config);
+    server.start();
+    // 📖 #ENDELIDE
+    println!('done');
+}
+// 📖 #END
+";
+        let res = Tree::default().add("test.rs", content).create().unwrap();
+        let patterns = vec![Pattern::default()];
+        let binding = res.join("test.rs");
+        let c = Content::new(binding.as_path(), &patterns).unwrap();
+
+        let results = c.extract().unwrap();
+        assert_eq!(
+            results[0].data,
+            "fn main() {\n    let config = Config::default();\n    // ...\n    println!('done');\n}"
+        );
     }
 
     #[test]
     fn can_create_content_metadata() {
         assert_debug_snapshot!(ContentMetadata::new("<id: second pattern >"));
         assert!(ContentMetadata::new("<second pattern >").is_none());
+        assert_eq!(
+            ContentMetadata::new("<id:example> <lang:rust>")
+                .and_then(|metadata| metadata.lang)
+                .as_deref(),
+            Some("rust")
+        );
     }
 }
@@ -1,18 +1,157 @@
 //! A module for defining patterns used in parsing and extracting content.
 //!
 //! This module provides functionality to define patterns used to identify
-//! content blocks in files.
+//! content blocks in files, and built-in [`Preset`] patterns per language
+//! family that are selected by the file extension.
+use std::{collections::HashMap, path::Path};
+
 use lazy_static::lazy_static;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 
 lazy_static! {
     static ref DEFAULT_START: Regex = Regex::new(r"//\s*📖\s*#START").unwrap();
-    static ref DEFAULT_END: Regex = Regex::new(r"//\s*📖\s*#END").unwrap();
+    static ref DEFAULT_END: Regex = Regex::new(r"//\s*📖\s*#END\b").unwrap();
+    static ref DEFAULT_INCLUDE: Regex = Regex::new(r"//\s*📖\s*#INCLUDE").unwrap();
+    static ref DEFAULT_HIDE: Regex = Regex::new(r"//\s*📖\s*#HIDE").unwrap();
+    static ref DEFAULT_SHOW: Regex = Regex::new(r"//\s*📖\s*#SHOW").unwrap();
+    static ref DEFAULT_HIDE_LINE: Regex = Regex::new(r"//\s*📖\s*hide\s*$").unwrap();
+    static ref DEFAULT_ELIDE: Regex = Regex::new(r"//\s*📖\s*#ELIDE").unwrap();
+    static ref DEFAULT_END_ELIDE: Regex = Regex::new(r"//\s*📖\s*#ENDELIDE").unwrap();
+    static ref DEFAULT_METADATA: Regex = Regex::new(r"<id:(?P<id>.*?)>").unwrap();
     static ref DEFAULT_CLEANUPS: Vec<Regex> = vec![
         #[allow(clippy::trivial_regex)]
         Regex::new(r"//!").unwrap(),
     ];
+    static ref HASH_FILE_NAMES: Vec<Regex> =
+        vec![Regex::new(r"(^|/)(Dockerfile|Makefile|Gemfile|Rakefile)$").unwrap()];
+    static ref PRESET_PATTERNS: HashMap<Preset, Pattern> = Preset::ALL
+        .iter()
+        .map(|preset| (*preset, preset.build()))
+        .collect();
+}
+
+/// Enum representing the built-in patterns per language family. The block
+/// markers are written with the line comment syntax of the language, for
+/// example `# 📖 #START <id:setup>` in a Python file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Preset {
+    /// Rust files, `//` comments. Same as [`Pattern::default`].
+    Rust,
+    /// C, C++, Java, JavaScript, TypeScript, Go and friends, `//` comments.
+    CLike,
+    /// Python, Shell, YAML, TOML, Ruby and friends, `#` comments.
+    Hash,
+    /// SQL files, `--` comments.
+    Sql,
+    /// HTML, XML and Markdown files, `<!-- -->` comments.
+    Html,
+    /// Lua files, `--` comments.
+    Lua,
+    /// Lisp, Clojure, Scheme and Racket files, `;` comments.
+    Lisp,
+}
+
+impl Preset {
+    /// All the presets.
+    pub const ALL: [Self; 7] = [
+        Self::Rust,
+        Self::CLike,
+        Self::Hash,
+        Self::Sql,
+        Self::Html,
+        Self::Lua,
+        Self::Lisp,
+    ];
+
+    /// Returns the file extensions of the preset.
+    #[must_use]
+    pub const fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            Self::Rust => &["rs"],
+            Self::CLike => &[
+                "c", "h", "cc", "cpp", "cxx", "hpp", "hh", "java", "js", "mjs", "cjs", "jsx", "ts",
+                "tsx", "go", "cs", "swift", "kt", "kts", "scala", "dart", "php", "proto", "zig",
+            ],
+            Self::Hash => &[
+                "py", "sh", "bash", "zsh", "fish", "yaml", "yml", "toml", "rb", "pl", "r", "tf",
+                "nix", "ps1",
+            ],
+            Self::Sql => &["sql"],
+            Self::Html => &[
+                "html", "htm", "xml", "svg", "vue", "svelte", "md", "markdown",
+            ],
+            Self::Lua => &["lua"],
+            Self::Lisp => &[
+                "lisp", "lsp", "el", "clj", "cljs", "cljc", "edn", "scm", "ss", "rkt", "fnl",
+            ],
+        }
+    }
+
+    /// Returns the preset of the given file, by the file extension or by well
+    /// known file names like `Dockerfile`.
+    #[must_use]
+    pub fn from_path(path: &Path) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|preset| preset.pattern().applies_to(path))
+    }
+
+    /// Returns the pattern of the preset.
+    #[must_use]
+    pub fn pattern(&self) -> Pattern {
+        PRESET_PATTERNS
+            .get(self)
+            .cloned()
+            .unwrap_or_else(|| self.build())
+    }
+
+    fn build(self) -> Pattern {
+        let pattern = match self {
+            Self::Rust => Pattern::default(),
+            Self::CLike => Pattern::with_comment("//", "", "// ..."),
+            Self::Hash => Pattern {
+                paths: HASH_FILE_NAMES.to_owned(),
+                ..Pattern::with_comment("#", "", "# ...")
+            },
+            Self::Sql | Self::Lua => Pattern::with_comment("--", "", "-- ..."),
+            Self::Html => Pattern::with_comment("<!--", r"\s*-->", "..."),
+            Self::Lisp => Pattern::with_comment(";+", "", "; ..."),
+        };
+        Pattern {
+            extensions: self.extensions().iter().map(ToString::to_string).collect(),
+            ..pattern
+        }
+    }
+}
+
+/// Represents the presets that are added to the parser patterns: all the
+/// presets, none of them, or only the listed presets.
+///
+/// ```yaml
+/// presets: false
+/// presets: [hash, sql]
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Presets {
+    /// Enable or disable all the presets.
+    All(bool),
+    /// Enable only the given presets.
+    Only(Vec<Preset>),
+}
+
+impl Presets {
+    /// Returns the enabled presets.
+    #[must_use]
+    pub fn enabled(&self) -> &[Preset] {
+        match self {
+            Self::All(true) => &Preset::ALL,
+            Self::All(false) => &[],
+            Self::Only(presets) => presets,
+        }
+    }
 }
 
 /// Represents a pattern used for identifying content blocks in files.
@@ -28,6 +167,56 @@ pub struct Pattern {
     /// content block.
     #[serde(with = "serde_regex")]
     pub cleanups: Vec<Regex>,
+    /// The regular expression pattern to identify a line that includes another
+    /// content block by ID, for example `// 📖 #INCLUDE <id:setup>`.
+    #[serde(with = "serde_regex", default)]
+    pub include: Option<Regex>,
+    /// The regular expression pattern to identify the start of lines that are
+    /// hidden from the content block.
+    #[serde(with = "serde_regex", default)]
+    pub hide: Option<Regex>,
+    /// The regular expression pattern to identify the end of hidden lines.
+    #[serde(with = "serde_regex", default)]
+    pub show: Option<Regex>,
+    /// The regular expression pattern to identify a single line that is hidden
+    /// from the content block, for example `unwrap(); // 📖 hide`.
+    #[serde(with = "serde_regex", default)]
+    pub hide_line: Option<Regex>,
+    /// The regular expression pattern to identify the start of lines that are
+    /// collapsed into a single [`Pattern::elide_text`] line.
+    #[serde(with = "serde_regex", default)]
+    pub elide: Option<Regex>,
+    /// The regular expression pattern to identify the end of elided lines.
+    #[serde(with = "serde_regex", default)]
+    pub end_elide: Option<Regex>,
+    /// The placeholder text of elided lines.
+    #[serde(default = "default_elide_text")]
+    pub elide_text: String,
+    /// The regular expression pattern to extract the block metadata from the
+    /// start and include lines. The `id` named group is the block ID, the
+    /// `lang` named group is the block language and any other named group is
+    /// a block attribute. Defaults to `<id:(?P<id>.*?)>`.
+    #[serde(with = "serde_regex", default = "default_metadata")]
+    pub metadata: Regex,
+    /// The file extensions that the pattern applies to, for example `py`.
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    /// The regular expression patterns of the file paths that the pattern
+    /// applies to, matched against the path relative to the collect folder.
+    /// The pattern applies to files that match the extensions or the paths,
+    /// and to all files when both are empty.
+    #[serde(with = "serde_regex", default)]
+    pub paths: Vec<Regex>,
+}
+
+fn default_elide_text() -> String {
+    "// ...".to_string()
+}
+
+/// Returns the default metadata regex, which matches `<id:[ID]>`.
+#[must_use]
+pub fn default_metadata() -> Regex {
+    DEFAULT_METADATA.to_owned()
 }
 
 impl Default for Pattern {
@@ -38,11 +227,79 @@ impl Default for Pattern {
             start: DEFAULT_START.to_owned(),
             end: DEFAULT_END.to_owned(),
             cleanups: DEFAULT_CLEANUPS.to_owned(),
+            include: Some(DEFAULT_INCLUDE.to_owned()),
+            hide: Some(DEFAULT_HIDE.to_owned()),
+            show: Some(DEFAULT_SHOW.to_owned()),
+            hide_line: Some(DEFAULT_HIDE_LINE.to_owned()),
+            elide: Some(DEFAULT_ELIDE.to_owned()),
+            end_elide: Some(DEFAULT_END_ELIDE.to_owned()),
+            elide_text: default_elide_text(),
+            metadata: default_metadata(),
+            extensions: vec![],
+            paths: vec![],
         }
     }
 }
 
 impl Pattern {
+    /// Creates a [`Pattern`] where the block markers are written with the
+    /// given line comment regex, for example `#` for `# 📖 #START`. The
+    /// `suffix` regex closes the hide line marker, like `-->` in HTML.
+    ///
+    /// # Panics
+    /// When the given comment regexes are invalid
+    #[must_use]
+    pub fn with_comment(prefix: &str, suffix: &str, elide_text: &str) -> Self {
+        let marker = |keyword: &str| {
+            Regex::new(&format!(r"{prefix}\s*📖\s*{keyword}")).expect("valid comment regex")
+        };
+        Self {
+            start: marker("#START"),
+            end: marker(r"#END\b"),
+            cleanups: vec![],
+            include: Some(marker("#INCLUDE")),
+            hide: Some(marker("#HIDE")),
+            show: Some(marker("#SHOW")),
+            hide_line: Some(marker(&format!(r"hide{suffix}\s*$"))),
+            elide: Some(marker("#ELIDE")),
+            end_elide: Some(marker("#ENDELIDE")),
+            elide_text: elide_text.to_string(),
+            metadata: default_metadata(),
+            extensions: vec![],
+            paths: vec![],
+        }
+    }
+
+    /// Checks if the pattern applies to the given file, by the extensions and
+    /// paths filters.
+    #[must_use]
+    pub fn applies_to(&self, path: &Path) -> bool {
+        if self.extensions.is_empty() && self.paths.is_empty() {
+            return true;
+        }
+
+        let extension_match = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| {
+                self.extensions
+                    .iter()
+                    .any(|e| e.eq_ignore_ascii_case(extension))
+            });
+        if extension_match {
+            return true;
+        }
+
+        let path = path.to_string_lossy().replace('\\', "/");
+        self.paths.iter().any(|regex| regex.is_match(&path))
+    }
+
+    /// Checks if the metadata regex has the `id` named group.
+    #[must_use]
+    pub fn has_id_group(&self) -> bool {
+        self.metadata.capture_names().any(|name| name == Some("id"))
+    }
+
     /// Checks if the provided string matches the start pattern of the pattern.
     #[must_use]
     pub fn start_with(&self, str: &str) -> bool {
@@ -55,6 +312,70 @@ impl Pattern {
         self.end.is_match(str)
     }
 
+    /// Checks if the provided string matches the include pattern of the
+    /// pattern.
+    #[must_use]
+    pub fn include_with(&self, str: &str) -> bool {
+        self.include
+            .as_ref()
+            .is_some_and(|include| include.is_match(str))
+    }
+
+    /// Checks if the provided string matches the hide pattern of the pattern.
+    #[must_use]
+    pub fn hide_with(&self, str: &str) -> bool {
+        self.hide.as_ref().is_some_and(|hide| hide.is_match(str))
+    }
+
+    /// Checks if the provided string matches the show pattern of the pattern.
+    #[must_use]
+    pub fn show_with(&self, str: &str) -> bool {
+        self.show.as_ref().is_some_and(|show| show.is_match(str))
+    }
+
+    /// Checks if the provided string matches the hide line pattern of the
+    /// pattern.
+    #[must_use]
+    pub fn hide_line_with(&self, str: &str) -> bool {
+        self.hide_line
+            .as_ref()
+            .is_some_and(|hide_line| hide_line.is_match(str))
+    }
+
+    /// Checks if the provided string matches the elide pattern of the pattern.
+    #[must_use]
+    pub fn elide_with(&self, str: &str) -> bool {
+        self.elide.as_ref().is_some_and(|elide| elide.is_match(str))
+    }
+
+    /// Checks if the provided string matches the end elide pattern of the
+    /// pattern.
+    #[must_use]
+    pub fn end_elide_with(&self, str: &str) -> bool {
+        self.end_elide
+            .as_ref()
+            .is_some_and(|end_elide| end_elide.is_match(str))
+    }
+
+    /// Returns the elide placeholder line, indented as the given elide line.
+    #[must_use]
+    pub fn elide_placeholder(&self, line: &str) -> String {
+        let indent = &line[..line.len() - line.trim_start().len()];
+        format!("{indent}{}", self.elide_text)
+    }
+
+    /// Checks if the provided string is a directive line that changes the
+    /// extracted content, like include or hidden lines.
+    #[must_use]
+    pub fn is_directive(&self, str: &str) -> bool {
+        self.include_with(str)
+            || self.hide_with(str)
+            || self.show_with(str)
+            || self.hide_line_with(str)
+            || self.elide_with(str)
+            || self.end_elide_with(str)
+    }
+
     /// Applies cleanup operations defined in the pattern to the provided text.
     #[must_use]
     pub fn cleanup(&self, text: &str) -> String {
@@ -66,6 +387,68 @@ impl Pattern {
 
         text_result
     }
+
+    /// Reverses the cleanup operations of the given raw lines on the provided
+    /// text, by adding back the prefix that the cleanups removed from each raw
+    /// line (for example `//!`).
+    ///
+    /// Returns `None` when the cleanups can't be reversed, like when they
+    /// remove text that is not a prefix or the removed prefix is not the same
+    /// on all the lines.
+    #[must_use]
+    pub fn reverse_cleanup(&self, raw_lines: &[&str], text: &str) -> Option<String> {
+        let mut prefix: Option<&str> = None;
+        for raw_line in raw_lines {
+            let cleaned = self.cleanup(raw_line);
+            let line_prefix = raw_line.strip_suffix(cleaned.as_str())?;
+            let line_prefix = line_prefix.trim_start();
+            if line_prefix.is_empty() && cleaned.trim().is_empty() {
+                continue;
+            }
+            match prefix {
+                Some(prefix) if prefix != line_prefix => return None,
+                _ => prefix = Some(line_prefix),
+            }
+        }
+
+        // when the cleanups removed a prefix, the indentation before the prefix
+        // was removed as well.
+        let lead = match prefix {
+            Some(prefix) if !prefix.is_empty() => {
+                let indent = raw_lines
+                    .iter()
+                    .find(|line| !line.trim().is_empty())
+                    .map(|line| &line[..line.len() - line.trim_start().len()])
+                    .unwrap_or_default();
+                format!("{indent}{prefix}")
+            }
+            _ => String::new(),
+        };
+        // the extracted content is trimmed, keep the leading whitespace of the
+        // first line that follows the prefix.
+        let first_line_space = raw_lines
+            .iter()
+            .map(|line| self.cleanup(line))
+            .find(|line| !line.trim().is_empty())
+            .map(|line| line[..line.len() - line.trim_start().len()].to_string())
+            .unwrap_or_default();
+
+        Some(
+            text.lines()
+                .enumerate()
+                .map(|(index, line)| {
+                    if index == 0 {
+                        format!("{lead}{first_line_space}{line}")
+                    } else {
+                        format!("{lead}{line}")
+                    }
+                    .trim_end()
+                    .to_string()
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        )
+    }
 }
 
 #[cfg(test)]
@@ -80,6 +463,82 @@ mod tests {
         assert_debug_snapshot!(Pattern::default());
     }
 
+    #[test]
+    fn can_select_preset() {
+        assert_eq!(
+            Preset::from_path(Path::new("src/lib.rs")),
+            Some(Preset::Rust)
+        );
+        assert_eq!(Preset::from_path(Path::new("app.TSX")), Some(Preset::CLike));
+        assert_eq!(Preset::from_path(Path::new("ci.yml")), Some(Preset::Hash));
+        assert_eq!(
+            Preset::from_path(Path::new("Dockerfile")),
+            Some(Preset::Hash)
+        );
+        assert_eq!(
+            Preset::from_path(Path::new("schema.sql")),
+            Some(Preset::Sql)
+        );
+        assert_eq!(
+            Preset::from_path(Path::new("README.md")),
+            Some(Preset::Html)
+        );
+        assert_eq!(Preset::from_path(Path::new("init.lua")), Some(Preset::Lua));
+        assert_eq!(Preset::from_path(Path::new("core.clj")), Some(Preset::Lisp));
+        assert_eq!(Preset::from_path(Path::new("notes.txt")), None);
+    }
+
+    #[test]
+    fn applies_to() {
+        assert!(Pattern::default().applies_to(Path::new("README.md")));
+
+        let pattern = Pattern {
+            extensions: vec!["py".to_string()],
+            paths: vec![Regex::new(r"^scripts/").unwrap()],
+            ..Pattern::default()
+        };
+        assert!(pattern.applies_to(Path::new("src/main.PY")));
+        assert!(pattern.applies_to(Path::new("scripts/install")));
+        assert!(!pattern.applies_to(Path::new("README.md")));
+        assert!(!pattern.applies_to(Path::new("docs/scripts/install")));
+    }
+
+    #[test]
+    fn can_match_presets() {
+        let cases = [
+            (Preset::Rust, "//", ""),
+            (Preset::CLike, "//", ""),
+            (Preset::Hash, "#", ""),
+            (Preset::Sql, "--", ""),
+            (Preset::Html, "<!--", " -->"),
+            (Preset::Lua, "--", ""),
+            (Preset::Lisp, ";;", ""),
+        ];
+        for (preset, comment, suffix) in cases {
+            let pattern = preset.pattern();
+            let line = |keyword: &str| format!("    {comment} 📖 {keyword}{suffix}");
+
+            assert!(pattern.start_with(&line("#START <id:setup>")), "{preset:?}");
+            assert!(pattern.end_with(&line("#END")), "{preset:?}");
+            assert!(!pattern.end_with(&line("#ENDELIDE")), "{preset:?}");
+            assert!(
+                pattern.include_with(&line("#INCLUDE <id:setup>")),
+                "{preset:?}"
+            );
+            assert!(pattern.hide_with(&line("#HIDE")), "{preset:?}");
+            assert!(pattern.show_with(&line("#SHOW")), "{preset:?}");
+            assert!(pattern.elide_with(&line("#ELIDE")), "{preset:?}");
+            assert!(pattern.end_elide_with(&line("#ENDELIDE")), "{preset:?}");
+            assert!(
+                pattern.hide_line_with(&format!("run(); {comment} 📖 hide{suffix}")),
+                "{preset:?}"
+            );
+            assert!(!pattern.start_with("📖 #START <id:setup>"), "{preset:?}");
+        }
+
+        assert!(!Preset::Hash.pattern().start_with("// 📖 #START <id:setup>"));
+    }
+
     #[test]
     fn is_start_with() {
         let pattern = Pattern::default();
@@ -96,6 +555,35 @@ mod tests {
         assert!(pattern.end_with("// 📖   #END"));
         assert!(pattern.end_with("//📖#END <>"));
         assert!(!pattern.end_with("//📖"));
+        assert!(!pattern.end_with("// 📖 #ENDELIDE"));
+    }
+
+    #[test]
+    fn is_include_with() {
+        let pattern = Pattern::default();
+        assert!(pattern.include_with("    // 📖 #INCLUDE <id:setup>"));
+        assert!(pattern.include_with("//📖#INCLUDE <id:setup>"));
+        assert!(!pattern.include_with("// 📖 #START <id:setup>"));
+    }
+
+    #[test]
+    fn is_hide_with() {
+        let pattern = Pattern::default();
+        assert!(pattern.hide_with("    // 📖 #HIDE"));
+        assert!(pattern.show_with("    // 📖 #SHOW"));
+        assert!(pattern.hide_line_with("let a = b.unwrap(); // 📖 hide"));
+        assert!(pattern.hide_line_with("let a = b.unwrap(); //📖hide  "));
+        assert!(!pattern.hide_line_with("// 📖 hide me"));
+        assert!(!pattern.hide_line_with("    // 📖 #HIDE"));
+    }
+
+    #[test]
+    fn is_elide_with() {
+        let pattern = Pattern::default();
+        assert!(pattern.elide_with("    // 📖 #ELIDE"));
+        assert!(!pattern.elide_with("    // 📖 #ENDELIDE"));
+        assert!(pattern.end_elide_with("    // 📖 #ENDELIDE"));
+        assert_eq!(pattern.elide_placeholder("    // 📖 #ELIDE"), "    // ...");
     }
 
     #[test]
@@ -107,4 +595,28 @@ mod tests {
         assert_eq!(text.len(), DEFAULT_CLEANUPS.len());
         assert_eq!(pattern.cleanup(&text.join(" ")), "");
     }
+
+    #[test]
+    fn can_reverse_cleanup() {
+        let pattern = Pattern::default();
+        let raw_lines = ["//! ## Goal", "//!", "//! - **Alignment**"];
+        let text = pattern.cleanup(&raw_lines.join("\n"));
+
+        assert_eq!(
+            pattern.reverse_cleanup(&raw_lines, text.trim()),
+            Some(raw_lines.join("\n"))
+        );
+        assert_eq!(
+            pattern.reverse_cleanup(&raw_lines, "## Goals\n\n - **Alignment**"),
+            Some("//! ## Goals\n//!\n//! - **Alignment**".to_string())
+        );
+        assert_eq!(
+            pattern.reverse_cleanup(&["    fn main() {", "    }"], "fn run() {\n    }"),
+            Some("    fn run() {\n    }".to_string())
+        );
+        assert_eq!(
+            pattern.reverse_cleanup(&["//! doc", "fn main() {}"], "text"),
+            None
+        );
+    }
 }
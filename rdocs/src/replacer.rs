@@ -31,7 +31,8 @@ pub enum ReplaceStatus {
     Error(String),
     NotFound(String),
     Equal(String),
-    Replaced(String, String, String),
+    /// Replaced block: ID, new file content, new block and the previous block.
+    Replaced(String, String, String, String),
 }
 
 impl fmt::Display for ReplaceStatus {
@@ -40,7 +41,19 @@ impl fmt::Display for ReplaceStatus {
             Self::Error(_) => write!(f, "error"),
             Self::NotFound(_) => write!(f, "not found"),
             Self::Equal(_) => write!(f, "equal"),
-            Self::Replaced(_, _, _) => write!(f, "replaced"),
+            Self::Replaced(_, _, _, _) => write!(f, "replaced"),
+        }
+    }
+}
+
+impl ReplaceStatus {
+    /// Returns the block ID of the status. [`ReplaceStatus::Error`] holds an
+    /// error message and has no ID.
+    #[must_use]
+    pub fn id(&self) -> Option<&str> {
+        match self {
+            Self::Error(_) => None,
+            Self::NotFound(id) | Self::Equal(id) | Self::Replaced(id, _, _, _) => Some(id),
         }
     }
 }
@@ -55,9 +68,20 @@ pub struct Replace {
 #[derive(Debug)]
 pub struct ReplaceResult {
     pub path: PathBuf,
+    /// The 1-based line number of the start marker in the original file.
+    pub line: Option<usize>,
     pub status: ReplaceStatus,
 }
 
+/// Struct representing a replacement marker found in a target file.
+#[derive(Debug, Clone)]
+pub struct Marker {
+    pub id: String,
+    pub path: PathBuf,
+    /// The 1-based line number of the start marker.
+    pub line: usize,
+}
+
 impl Default for Replace {
     /// Creates a default instance of [`Replace`] with predefined start and end
     /// patterns.
@@ -90,7 +114,7 @@ impl Replace {
                     Ok(status) => Some(status),
                     Err(err) => {
                         tracing::error!(err = %err, "could not replace content");
-                        Some(vec![ReplaceResult{ path: path.clone(), status: ReplaceStatus::Error(err.to_string()) }])
+                        Some(vec![ReplaceResult{ path: path.clone(), line: None, status: ReplaceStatus::Error(err.to_string()) }])
                     }
                 }
 
@@ -119,7 +143,7 @@ impl Replace {
                     Ok((_, status)) => Some(status),
                     Err(err) => {
                         tracing::error!(err = %err, "could not replace content");
-                        Some(vec![ReplaceResult{ path: path.clone(), status: ReplaceStatus::Error(err.to_string()) }])
+                        Some(vec![ReplaceResult{ path: path.clone(), line: None, status: ReplaceStatus::Error(err.to_string()) }])
                     }
                 }
 
@@ -128,6 +152,52 @@ impl Replace {
             .collect::<Vec<_>>()
     }
 
+    /// Collects all the replacement markers found in the files of the given
+    /// collector.
+    #[must_use]
+    pub fn markers(&self, collector: &Collector) -> Vec<Marker> {
+        let files = collector.collect_files();
+        files
+            .par_iter()
+            .flat_map(|path| {
+                let span = tracing::span!(tracing::Level::TRACE, "markers", path = %path.display());
+                let _guard = span.enter();
+
+                match self.find_markers(path) {
+                    Ok(markers) => markers,
+                    Err(err) => {
+                        tracing::debug!(err = %err, "could not find markers");
+                        vec![]
+                    }
+                }
+            })
+            .collect::<Vec<_>>()
+    }
+
+    /// Find all the start replacement markers in the given file.
+    ///
+    /// # Errors
+    /// When could not read the file or the start pattern is not a valid regex
+    pub fn find_markers(&self, path: &Path) -> ReplacerResult<Vec<Marker>> {
+        let content = std::fs::read_to_string(path)?;
+        let re = Regex::new(&self.start.replace("ID", ".+?"))?;
+
+        Ok(content
+            .lines()
+            .enumerate()
+            .flat_map(|(line_index, line)| {
+                re.captures_iter(line)
+                    .filter_map(|capture| capture.get(2))
+                    .map(|id| Marker {
+                        id: id.as_str().to_string(),
+                        path: path.to_path_buf(),
+                        line: line_index + 1,
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect())
+    }
+
     /// Execute replace block content and save the new content to the given
     /// path.
     ///
@@ -143,7 +213,7 @@ impl Replace {
 
         let is_changed = status
             .iter()
-            .any(|s| matches!(s.status, ReplaceStatus::Replaced(_, _, _)));
+            .any(|s| matches!(s.status, ReplaceStatus::Replaced(_, _, _, _)));
 
         if is_changed {
             let mut file = File::create(path)?;
@@ -155,7 +225,8 @@ impl Replace {
     }
 
     /// Execute replace block content and save the new content to the given
-    /// path.
+    /// path. A block of a namespaced ID is also replaced in the markers of
+    /// its short ID, when the short ID is unique.
     ///
     /// # Errors
     /// When when could not read the file or could not capture the pattern
@@ -165,15 +236,26 @@ impl Replace {
         path: &Path,
         parse_contents: &Vec<parser::ContentResults>,
     ) -> ReplacerResult<(String, Vec<ReplaceResult>)> {
-        let mut content = std::fs::read_to_string(path)?;
+        let original = std::fs::read_to_string(path)?;
+        let mut content = original.clone();
         let mut results = vec![];
         for parse_content in parse_contents {
-            let status = self.find_and_replace(&content, parse_content)?;
-            if let ReplaceStatus::Replaced(_, all_content, _) = &status {
-                content = all_content.to_string();
+            let block_id = &parse_content.metadata.id;
+            let mut status = ReplaceStatus::NotFound(block_id.clone());
+            let mut line = None;
+            for id in parse_content.metadata.reference_ids(parse_contents) {
+                status = self.find_and_replace(&content, id, block_id, &parse_content.data)?;
+                if !matches!(status, ReplaceStatus::NotFound(_)) {
+                    line = self.marker_line(&original, id)?;
+                    break;
+                }
+            }
+            if let ReplaceStatus::Replaced(_, all_content, _, _) = &status {
+                content.clone_from(all_content);
             }
             results.push(ReplaceResult {
                 path: path.to_path_buf(),
+                line,
                 status,
             });
         }
@@ -181,13 +263,39 @@ impl Replace {
         Ok((content, results))
     }
 
+    /// Returns the trimmed content between the start and end markers of the
+    /// given ID.
+    ///
+    /// # Errors
+    /// When the start or end patterns are not a valid regex
+    pub fn find_block(&self, content: &str, id: &str) -> ReplacerResult<Option<String>> {
+        let start_re_pattern = self.start.replace("ID", id);
+        let end_re_pattern = self.end.replace("ID", id);
+        let re = Regex::new(&format!("(?s){start_re_pattern}(.*){end_re_pattern}"))?;
+
+        Ok(re
+            .captures(content)
+            .and_then(|capture| capture.get(3))
+            .map(|block| block.as_str().trim().to_string()))
+    }
+
+    /// Returns the 1-based line number of the first start marker of the given
+    /// ID in the content.
+    fn marker_line(&self, content: &str, id: &str) -> ReplacerResult<Option<usize>> {
+        let re = Regex::new(&self.start.replace("ID", id))?;
+        Ok(re
+            .find(content)
+            .map(|found| content[..found.start()].lines().count() + 1))
+    }
+
     /// Find and replace the content between two patterns based on capturing
     /// details.
     ///
-    /// This function takes gets [`parser::ContentResults`] collected data,
-    /// which contains the results of parsing the content. It searches for a
-    /// pattern match in the given content and replaces the content between
-    /// specified capturing groups.
+    /// This function takes the marker ID, the block ID and the collected block
+    /// data. It searches for a pattern match in the given content and replaces
+    /// the content between specified capturing groups. The returned status
+    /// holds the block ID, which differs from the marker ID when the marker
+    /// uses the short ID of a namespaced block.
     ///
     /// # Errors
     ///
@@ -197,21 +305,22 @@ impl Replace {
     fn find_and_replace(
         &self,
         content: &str,
-        parse_content: &parser::ContentResults,
+        id: &str,
+        block_id: &str,
+        data: &str,
     ) -> ReplacerResult<ReplaceStatus> {
-        let start_re_pattern = self.start.replace("ID", &parse_content.metadata.id);
-        let end_re_pattern = self.end.replace("ID", &parse_content.metadata.id);
+        let start_re_pattern = self.start.replace("ID", id);
+        let end_re_pattern = self.end.replace("ID", id);
         let re = Regex::new(&format!("(?s){start_re_pattern}(.*){end_re_pattern}"))?;
 
         if let Some(capture) = re.captures(content) {
-            if capture
+            let previous = capture
                 .get(3)
                 .ok_or(ReplacerError::CaptureNotFound { index: 3 })?
                 .as_str()
-                .trim()
-                == parse_content.data
-            {
-                return Ok(ReplaceStatus::Equal(parse_content.metadata.id.to_string()));
+                .trim();
+            if previous == data {
+                return Ok(ReplaceStatus::Equal(block_id.to_string()));
             }
 
             let keep_start = capture
@@ -223,24 +332,23 @@ impl Replace {
                 .ok_or(ReplacerError::CaptureNotFound { index: 4 })?
                 .as_str();
 
-            let replace = format!("{}\n{}\n{}", keep_start, &parse_content.data, keep_end);
+            let replace = format!("{keep_start}\n{data}\n{keep_end}");
             return Ok(ReplaceStatus::Replaced(
-                parse_content.metadata.id.to_string(),
+                block_id.to_string(),
                 re.replace_all(content, &replace).to_string(),
-                parse_content.data.to_string(),
+                data.to_string(),
+                previous.to_string(),
             ));
         }
 
-        Ok(ReplaceStatus::NotFound(
-            parse_content.metadata.id.to_string(),
-        ))
+        Ok(ReplaceStatus::NotFound(block_id.to_string()))
     }
 }
 
 #[cfg(test)]
 mod tests {
 
-    use std::path::PathBuf;
+    use std::{collections::BTreeMap, path::PathBuf};
 
     use insta::{assert_debug_snapshot, with_settings};
 
@@ -279,14 +387,24 @@ mod tests {
             parser::ContentResults {
                 metadata: parser::ContentMetadata {
                     id: "REPLACE-1".to_string(),
+                    lang: None,
+                    attributes: BTreeMap::new(),
+                    namespace: None,
                 },
                 data: "NEW CONTENT1".to_string(),
+                relative_path: String::new(),
+                location: parser::Location::default(),
             },
             parser::ContentResults {
                 metadata: parser::ContentMetadata {
                     id: "REPLACE-2".to_string(),
+                    lang: None,
+                    attributes: BTreeMap::new(),
+                    namespace: None,
                 },
                 data: "NEW CONTENT2".to_string(),
+                relative_path: String::new(),
+                location: parser::Location::default(),
             },
         ];
 
@@ -308,14 +426,24 @@ mod tests {
             parser::ContentResults {
                 metadata: parser::ContentMetadata {
                     id: "REPLACE-1".to_string(),
+                    lang: None,
+                    attributes: BTreeMap::new(),
+                    namespace: None,
                 },
                 data: "NEW CONTENT1".to_string(),
+                relative_path: String::new(),
+                location: parser::Location::default(),
             },
             parser::ContentResults {
                 metadata: parser::ContentMetadata {
                     id: "REPLACE-2".to_string(),
+                    lang: None,
+                    attributes: BTreeMap::new(),
+                    namespace: None,
                 },
                 data: "NEW CONTENT2".to_string(),
+                relative_path: String::new(),
+                location: parser::Location::default(),
             },
         ];
 
@@ -332,4 +460,64 @@ mod tests {
         });
         assert_debug_snapshot!(std::fs::read_to_string(data.join("README.md")).unwrap());
     }
+
+    #[test]
+    fn can_find_markers() {
+        let replacer = Replace::default();
+        let data = get_mock_data();
+
+        let markers = replacer
+            .find_markers(data.join("README.md").as_path())
+            .unwrap()
+            .into_iter()
+            .map(|marker| (marker.id, marker.line))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            markers,
+            vec![("REPLACE-1".to_string(), 3), ("REPLACE-2".to_string(), 12)]
+        );
+    }
+
+    #[test]
+    fn can_replace_short_ids() {
+        let replacer = Replace::default();
+        let content = |namespace: &str, id: &str, data: &str| {
+            let mut content = parser::ContentResults::new_for_test(id, data, "", 0, 0);
+            content.metadata.qualify(namespace);
+            content
+        };
+        let contents = vec![
+            content("first", "quick-start", "first quick start"),
+            content("second", "quick-start", "second quick start"),
+            content("first", "install", "install"),
+        ];
+
+        let res = tree_fs::Tree::default()
+            .add(
+                "README.md",
+                "<!-- 📖second::quick-start -->\n<!-- second::quick-start📖 -->\n<!-- \
+                 📖install -->\n<!-- install📖 -->\n<!-- 📖quick-start -->\n<!-- \
+                 quick-start📖 -->\n",
+            )
+            .create()
+            .unwrap();
+
+        let (content, results) = replacer
+            .replace(res.join("README.md").as_path(), &contents)
+            .unwrap();
+        assert_eq!(
+            results
+                .iter()
+                .map(|result| result.status.to_string())
+                .collect::<Vec<_>>(),
+            vec!["not found", "replaced", "replaced"]
+        );
+        assert_eq!(
+            content,
+            "<!-- 📖second::quick-start -->\nsecond quick start\n<!-- \
+             second::quick-start📖 -->\n<!-- 📖install -->\ninstall\n<!-- install📖 -->\n<!-- \
+             📖quick-start -->\n<!-- quick-start📖 -->\n"
+        );
+    }
 }
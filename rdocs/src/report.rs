@@ -0,0 +1,144 @@
+//! A module for cross-referencing collected blocks with replacement markers.
+//!
+//! This module provides functionality to find source blocks that are never
+//! injected into a target, target markers without a matching source block and
+//! how many times each block is used by each target file.
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::PathBuf,
+};
+
+use crate::{parser::ContentResults, replacer::Marker};
+
+/// Represents a source block that is not referenced by any target marker.
+#[derive(Debug)]
+pub struct UnusedSource {
+    pub id: String,
+    pub path: PathBuf,
+    pub line: usize,
+}
+
+/// Represents the number of times a block is referenced in a target file.
+#[derive(Debug)]
+pub struct Usage {
+    pub id: String,
+    pub path: PathBuf,
+    pub count: usize,
+}
+
+/// Represents the cross-reference report between source blocks and target
+/// markers.
+#[derive(Debug, Default)]
+pub struct Report {
+    /// Source blocks that are not referenced by any target marker.
+    pub unused_sources: Vec<UnusedSource>,
+    /// Target markers without a matching source block.
+    pub dangling_targets: Vec<Marker>,
+    /// Usage count of each block per target file.
+    pub usages: Vec<Usage>,
+}
+
+impl Report {
+    /// Creates a new [`Report`] from the collected source blocks and the
+    /// target markers.
+    #[must_use]
+    pub fn new(sources: &[ContentResults], markers: &[Marker]) -> Self {
+        let source_ids = sources
+            .iter()
+            .flat_map(|source| source.metadata.reference_ids(sources))
+            .collect::<HashSet<_>>();
+        let target_ids = markers
+            .iter()
+            .map(|marker| marker.id.as_str())
+            .collect::<HashSet<_>>();
+
+        let mut unused_sources = sources
+            .iter()
+            .filter(|source| {
+                !source
+                    .metadata
+                    .reference_ids(sources)
+                    .iter()
+                    .any(|id| target_ids.contains(id))
+            })
+            .map(|source| UnusedSource {
+                id: source.metadata.id.clone(),
+                path: source.location.path.clone(),
+                line: source.location.start_line,
+            })
+            .collect::<Vec<_>>();
+        unused_sources.sort_by(|a, b| a.id.cmp(&b.id).then(a.path.cmp(&b.path)));
+
+        let mut dangling_targets = markers
+            .iter()
+            .filter(|marker| !source_ids.contains(marker.id.as_str()))
+            .cloned()
+            .collect::<Vec<_>>();
+        dangling_targets.sort_by(|a, b| a.path.cmp(&b.path).then(a.line.cmp(&b.line)));
+
+        let mut counts: BTreeMap<(&PathBuf, &str), usize> = BTreeMap::new();
+        for marker in markers
+            .iter()
+            .filter(|marker| source_ids.contains(marker.id.as_str()))
+        {
+            *counts
+                .entry((&marker.path, marker.id.as_str()))
+                .or_default() += 1;
+        }
+        let usages = counts
+            .into_iter()
+            .map(|((path, id), count)| Usage {
+                id: id.to_string(),
+                path: path.clone(),
+                count,
+            })
+            .collect();
+
+        Self {
+            unused_sources,
+            dangling_targets,
+            usages,
+        }
+    }
+
+    /// Returns `true` when there are unused sources or dangling targets.
+    #[must_use]
+    pub const fn has_issues(&self) -> bool {
+        !self.unused_sources.is_empty() || !self.dangling_targets.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use insta::assert_debug_snapshot;
+
+    use super::*;
+
+    fn source(id: &str) -> ContentResults {
+        ContentResults::new_for_test(id, "", "src/lib.rs", 1, 3)
+    }
+
+    fn marker(id: &str, path: &str, line: usize) -> Marker {
+        Marker {
+            id: id.to_string(),
+            path: PathBuf::from(path),
+            line,
+        }
+    }
+
+    #[test]
+    fn can_create_report() {
+        let sources = vec![source("used"), source("unused")];
+        let markers = vec![
+            marker("used", "README.md", 1),
+            marker("used", "README.md", 10),
+            marker("used", "docs/guide.md", 4),
+            marker("missing", "docs/guide.md", 8),
+        ];
+
+        let report = Report::new(&sources, &markers);
+        assert!(report.has_issues());
+        assert_debug_snapshot!(report);
+    }
+}
@@ -0,0 +1,290 @@
+//! A module for rendering replace results in machine-readable formats
+//!
+//! This module provides functionality to serialize [`ReplaceResult`] to
+//! formats that CI systems can consume, like JSON, YAML, SARIF and `JUnit`.
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+use serde_json::json;
+
+use crate::{
+    errors::ResultsResult,
+    replacer::{ReplaceResult, ReplaceStatus},
+};
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const OUTDATED_RULE_ID: &str = "outdated-block";
+const ERROR_RULE_ID: &str = "replace-error";
+const SRCROOT_BASE_ID: &str = "%SRCROOT%";
+
+/// Enum representing different replace results formats.
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[derive(Clone)]
+pub enum Format {
+    /// Export in JSON format.
+    Json,
+    /// Export in YAML format.
+    Yaml,
+    /// Export in SARIF format.
+    Sarif,
+    /// Export in `JUnit` XML format.
+    Junit,
+}
+
+/// Represents a serializable replace result record.
+#[derive(Debug, Serialize)]
+pub struct Record {
+    pub path: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl From<&ReplaceResult> for Record {
+    fn from(result: &ReplaceResult) -> Self {
+        let (before, after, error) = match &result.status {
+            ReplaceStatus::Error(err) => (None, None, Some(err.clone())),
+            ReplaceStatus::NotFound(_) | ReplaceStatus::Equal(_) => (None, None, None),
+            ReplaceStatus::Replaced(_, _, block, previous) => {
+                (Some(previous.clone()), Some(block.clone()), None)
+            }
+        };
+
+        Self {
+            path: result.path.clone(),
+            line: result.line,
+            id: result.status.id().map(ToString::to_string),
+            status: result.status.to_string(),
+            before,
+            after,
+            error,
+        }
+    }
+}
+
+impl Format {
+    /// Render the given replace results. [`ReplaceStatus::NotFound`] results
+    /// are skipped.
+    ///
+    /// When `check` is `true` replaced blocks are reported as failures, since
+    /// the target content is outdated. The paths are relative to the given
+    /// base folder when the file is located under it.
+    ///
+    /// # Errors
+    ///
+    /// When could not serialize the results to the given format
+    pub fn render(
+        &self,
+        results: &[ReplaceResult],
+        check: bool,
+        base: Option<&Path>,
+    ) -> ResultsResult<String> {
+        let records = results
+            .iter()
+            .filter(|r| !matches!(r.status, ReplaceStatus::NotFound(_)))
+            .map(|result| {
+                let mut record = Record::from(result);
+                if let Some(path) = base.and_then(|base| record.path.strip_prefix(base).ok()) {
+                    record.path = path.to_path_buf();
+                }
+                record
+            })
+            .collect::<Vec<_>>();
+
+        Ok(match self {
+            Self::Json => serde_json::to_string_pretty(&records)?,
+            Self::Yaml => serde_yaml::to_string(&records)?,
+            Self::Sarif => serde_json::to_string_pretty(&sarif(&records, check))?,
+            Self::Junit => junit(&records, check),
+        })
+    }
+}
+
+fn sarif(records: &[Record], check: bool) -> serde_json::Value {
+    let results = records
+        .iter()
+        .filter_map(|record| {
+            let (rule_id, level, message) = match (&record.error, &record.after) {
+                (Some(err), _) => (ERROR_RULE_ID, "error", err.clone()),
+                (None, Some(_)) => (
+                    OUTDATED_RULE_ID,
+                    if check { "error" } else { "note" },
+                    format!(
+                        "Block `{}` is outdated",
+                        record.id.as_deref().unwrap_or_default()
+                    ),
+                ),
+                (None, None) => return None,
+            };
+
+            // relative paths are resolved by the SARIF consumer against the
+            // source root
+            let mut artifact_location = json!({ "uri": record.path.display().to_string() });
+            if record.path.is_relative() {
+                artifact_location["uriBaseId"] = json!(SRCROOT_BASE_ID);
+            }
+            let mut physical_location = json!({ "artifactLocation": artifact_location });
+            if let Some(line) = record.line {
+                physical_location["region"] = json!({ "startLine": line });
+            }
+
+            Some(json!({
+                "ruleId": rule_id,
+                "level": level,
+                "message": { "text": message },
+                "locations": [{ "physicalLocation": physical_location }],
+            }))
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": env!("CARGO_PKG_HOMEPAGE"),
+                    "rules": [
+                        {
+                            "id": OUTDATED_RULE_ID,
+                            "shortDescription": { "text": "Documentation block is not aligned with the source code" },
+                        },
+                        {
+                            "id": ERROR_RULE_ID,
+                            "shortDescription": { "text": "Could not replace documentation block" },
+                        },
+                    ],
+                },
+            },
+            "results": results,
+        }],
+    })
+}
+
+fn junit(records: &[Record], check: bool) -> String {
+    let errors = records.iter().filter(|r| r.error.is_some()).count();
+    let failures = if check {
+        records.iter().filter(|r| r.after.is_some()).count()
+    } else {
+        0
+    };
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = write!(
+        xml,
+        "<testsuites name=\"{name}\" tests=\"{tests}\" failures=\"{failures}\" \
+         errors=\"{errors}\">\n  <testsuite name=\"{name}\" tests=\"{tests}\" \
+         failures=\"{failures}\" errors=\"{errors}\">\n",
+        name = env!("CARGO_PKG_NAME"),
+        tests = records.len(),
+    );
+
+    for record in records {
+        let name = record.id.as_deref().unwrap_or("error");
+        let classname = record.path.display().to_string();
+        let _ = write!(
+            xml,
+            "    <testcase name=\"{}\" classname=\"{}\"",
+            escape_xml(name),
+            escape_xml(&classname)
+        );
+
+        let _ = match (&record.error, &record.after) {
+            (Some(err), _) => write!(
+                xml,
+                ">\n      <error message=\"{}\"/>\n    </testcase>\n",
+                escape_xml(err)
+            ),
+            (None, Some(after)) if check => write!(
+                xml,
+                ">\n      <failure message=\"block is outdated\">{}</failure>\n    </testcase>\n",
+                escape_xml(after)
+            ),
+            _ => xml.write_str("/>\n"),
+        };
+    }
+
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+
+    use insta::assert_snapshot;
+
+    use super::*;
+
+    fn get_results() -> Vec<ReplaceResult> {
+        vec![
+            ReplaceResult {
+                path: PathBuf::from("/repo/README.md"),
+                line: Some(3),
+                status: ReplaceStatus::Replaced(
+                    "quick-start".to_string(),
+                    String::new(),
+                    "cargo install rdocs".to_string(),
+                    "cargo install <old>".to_string(),
+                ),
+            },
+            ReplaceResult {
+                path: PathBuf::from("/repo/README.md"),
+                line: Some(10),
+                status: ReplaceStatus::Equal("installation".to_string()),
+            },
+            ReplaceResult {
+                path: PathBuf::from("/repo/README.md"),
+                line: None,
+                status: ReplaceStatus::NotFound("usage".to_string()),
+            },
+            ReplaceResult {
+                path: PathBuf::from("/repo/docs/guide.md"),
+                line: None,
+                status: ReplaceStatus::Error("permission denied".to_string()),
+            },
+        ]
+    }
+
+    #[test]
+    fn can_render_json() {
+        assert_snapshot!(Format::Json
+            .render(&get_results(), false, Some(Path::new("/repo")))
+            .unwrap());
+    }
+
+    #[test]
+    fn can_render_sarif() {
+        assert_snapshot!(Format::Sarif
+            .render(&get_results(), true, Some(Path::new("/repo")))
+            .unwrap()
+            .replace(env!("CARGO_PKG_VERSION"), "[VERSION]"));
+    }
+
+    #[test]
+    fn can_render_junit() {
+        assert_snapshot!(Format::Junit
+            .render(&get_results(), true, Some(Path::new("/repo")))
+            .unwrap());
+    }
+}
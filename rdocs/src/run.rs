@@ -0,0 +1,274 @@
+//! A module for running the rdocs flows from the library.
+//!
+//! The functions of this module are the building blocks of the CLI commands,
+//! and can be used to embed rdocs in other tools or build scripts without the
+//! `cli` feature.
+//!
+//! ```rust,no_run
+//! use std::path::Path;
+//!
+//! use rdocs::{config::Config, run::ExtractOptions};
+//!
+//! let config = Config::load(Some(Path::new("rdocs.yaml"))).unwrap();
+//! let report = rdocs::run_replace(
+//!     &config,
+//!     Path::new("src"),
+//!     Path::new("docs"),
+//!     true,
+//!     ExtractOptions::default(),
+//! )
+//! .unwrap();
+//! assert_eq!(report.outdated().count(), 0);
+//! ```
+use std::path::Path;
+
+use crate::{
+    collect::Collector,
+    command::{self, CacheMode},
+    config::Config,
+    errors::{RunError, RunResult},
+    parser::{ContentResults, Extraction, Parser},
+    replacer::{Replace, ReplaceResult, ReplaceStatus},
+    sync, template, verify,
+};
+
+/// Options for extracting the documentation blocks.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ExtractOptions {
+    /// How the recorded command outputs are used.
+    pub command_cache: CacheMode,
+    /// Verify the blocks before using them.
+    pub verify: bool,
+}
+
+impl ExtractOptions {
+    /// Returns the options of commands that update the documentation. The
+    /// command outputs are recorded unless the recorded outputs are used.
+    #[must_use]
+    pub const fn new(cached_commands: bool, verify: bool) -> Self {
+        Self {
+            command_cache: if cached_commands {
+                CacheMode::Use
+            } else {
+                CacheMode::Record
+            },
+            verify,
+        }
+    }
+}
+
+/// Represents the result of collecting the documentation blocks.
+#[derive(Debug)]
+pub struct CollectReport {
+    /// The extracted blocks.
+    pub blocks: Vec<ContentResults>,
+    /// The commands that could not run, their blocks are missing.
+    pub command_failures: Vec<command::Failure>,
+}
+
+/// Represents the result of replacing the documentation blocks.
+#[derive(Debug)]
+pub struct ReplaceReport {
+    /// A result per replaced block and rendered template, sorted by file
+    /// name.
+    pub results: Vec<ReplaceResult>,
+}
+
+impl ReplaceReport {
+    /// Returns the results of the blocks that were replaced or failed, which
+    /// are the outdated blocks when the report was created with `dry_run`.
+    pub fn outdated(&self) -> impl Iterator<Item = &ReplaceResult> {
+        self.results.iter().filter(|result| {
+            matches!(
+                result.status,
+                ReplaceStatus::Error(_) | ReplaceStatus::Replaced(_, _, _, _)
+            )
+        })
+    }
+
+    /// Returns the results of the blocks that failed.
+    pub fn errors(&self) -> impl Iterator<Item = &ReplaceResult> {
+        self.results
+            .iter()
+            .filter(|result| matches!(result.status, ReplaceStatus::Error(_)))
+    }
+}
+
+/// Collect the documentation blocks of the given folder.
+///
+/// # Errors
+/// When the collector could not be created, or when the verification is
+/// enabled and could not run or some blocks failed the verification
+pub fn run_collect(
+    config: &Config,
+    folder: &Path,
+    options: ExtractOptions,
+) -> RunResult<CollectReport> {
+    let collector =
+        Collector::from_config(folder, &config.collector).map_err(RunError::Collector)?;
+    let parser =
+        Parser::with_config(config.parser.clone()).with_command_cache(options.command_cache);
+    let Extraction {
+        blocks,
+        command_failures,
+    } = parser.extract(&collector);
+
+    if options.verify {
+        let failures = verify::Verifier::new(config.verify.clone()).verify(&blocks)?;
+        if !failures.is_empty() {
+            return Err(RunError::VerifyFailed { failures });
+        }
+    }
+
+    Ok(CollectReport {
+        blocks,
+        command_failures,
+    })
+}
+
+/// Collect the documentation blocks of the collect folder and create the
+/// collector of the replace folder.
+///
+/// # Errors
+/// See [`run_collect`]
+pub fn prepare(
+    config: &Config,
+    collect_folder: &Path,
+    replace_folder: &Path,
+    options: ExtractOptions,
+) -> RunResult<(CollectReport, Collector)> {
+    let report = run_collect(config, collect_folder, options)?;
+    let collector =
+        Collector::from_config(replace_folder, &config.collector).map_err(RunError::Collector)?;
+    Ok((report, collector))
+}
+
+/// Collect the documentation blocks and replace them in the replace folder.
+///
+/// The templates of the replace folder are rendered as well. When `dry_run`
+/// is `true` the replace results are calculated without changing the files.
+/// Commands that could not run are reported as errors.
+///
+/// # Errors
+/// See [`run_collect`]
+pub fn run_replace(
+    config: &Config,
+    collect_folder: &Path,
+    replace_folder: &Path,
+    dry_run: bool,
+    options: ExtractOptions,
+) -> RunResult<ReplaceReport> {
+    let (
+        CollectReport {
+            blocks,
+            command_failures,
+        },
+        collector,
+    ) = prepare(config, collect_folder, replace_folder, options)?;
+
+    let mut results = if dry_run {
+        Replace::default().stats(&collector, &blocks)
+    } else {
+        let results = Replace::default().replace_content(&collector, &blocks);
+        record_sync_state(&collector.folder, &blocks, &results);
+        results
+    };
+    results.extend(template::render_templates(&collector, &blocks, dry_run));
+    results.extend(command_failures.into_iter().map(|failure| ReplaceResult {
+        path: failure.path,
+        line: None,
+        status: ReplaceStatus::Error(failure.error.to_string()),
+    }));
+    results.sort_by(|a, b| a.path.file_name().cmp(&b.path.file_name()));
+    Ok(ReplaceReport { results })
+}
+
+/// Record the synced blocks in the sync state of the replace folder. The
+/// state is recorded only when the state file was created by a pull, and is
+/// written only when the synced blocks changed.
+pub fn record_sync_state(
+    folder: &Path,
+    contents: &[ContentResults],
+    replace_results: &[ReplaceResult],
+) {
+    if !sync::SyncState::exists(folder) {
+        return;
+    }
+    let result = sync::SyncState::load(folder).and_then(|mut state| {
+        if state.record(contents, replace_results) {
+            state.save(folder)?;
+        }
+        Ok(())
+    });
+    if let Err(err) = result {
+        tracing::warn!(err = %err, "could not save sync state");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use tree_fs::Tree;
+
+    use super::*;
+
+    #[test]
+    fn can_run_replace() {
+        let res = Tree::default()
+            .add(
+                "src/lib.rs",
+                "// 📖 #START <id:sum>\nlet sum = 1 + 2;\n// 📖 #END\n",
+            )
+            .add("docs/README.md", "<!-- 📖sum -->\nold\n<!-- sum📖 -->\n")
+            .create()
+            .unwrap();
+        let config = Config::default();
+
+        let report = run_collect(&config, &res.join("src"), ExtractOptions::default()).unwrap();
+        assert_eq!(report.blocks.len(), 1);
+
+        let report = run_replace(
+            &config,
+            &res.join("src"),
+            &res.join("docs"),
+            true,
+            ExtractOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(report.outdated().count(), 1);
+        assert_eq!(report.errors().count(), 0);
+        assert!(!res.join("docs").join(sync::SYNC_STATE_FILE).exists());
+
+        run_replace(
+            &config,
+            &res.join("src"),
+            &res.join("docs"),
+            false,
+            ExtractOptions::default(),
+        )
+        .unwrap();
+        // the sync state is recorded only after a pull created it
+        assert!(!res.join("docs").join(sync::SYNC_STATE_FILE).exists());
+        let report = run_replace(
+            &config,
+            &res.join("src"),
+            &res.join("docs"),
+            true,
+            ExtractOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(report.outdated().count(), 0);
+
+        sync::SyncState::default().save(&res.join("docs")).unwrap();
+        run_replace(
+            &config,
+            &res.join("src"),
+            &res.join("docs"),
+            false,
+            ExtractOptions::default(),
+        )
+        .unwrap();
+        let state = sync::SyncState::load(&res.join("docs")).unwrap();
+        assert_eq!(state.get("sum"), Some("let sum = 1 + 2;"));
+    }
+}
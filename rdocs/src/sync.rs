@@ -0,0 +1,469 @@
+//! A module for synchronizing target edits back into the source code.
+//!
+//! This module keeps track of the block content that was last synced to the
+//! target files, and provides functionality to write target blocks that were
+//! edited directly in the documentation back into the source file between the
+//! start and end patterns.
+//!
+//! The sync state file is created by the first pull, which records the blocks
+//! that are in sync. From then on, replacing the blocks updates the state.
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::{SyncError, SyncResult},
+    parser::ContentResults,
+    pattern::Pattern,
+    replacer::{Marker, Replace, ReplaceResult, ReplaceStatus},
+    vars,
+};
+
+/// The sync state file name, saved in the replace folder.
+pub const SYNC_STATE_FILE: &str = ".rdocs-sync.json";
+
+/// Represents the block content that was last synced to the target files.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SyncState {
+    blocks: BTreeMap<String, String>,
+}
+
+/// Represents a block found in a target file.
+#[derive(Debug)]
+pub struct TargetBlock {
+    pub marker: Marker,
+    pub content: String,
+}
+
+/// Enum representing the status of a pull operation.
+#[derive(Debug)]
+pub enum PullStatus {
+    /// The target block is equal to the source block.
+    Equal(String),
+    /// The target block was written back to the source: ID and the new block.
+    Pulled(String, String),
+    /// The source block changed since the last sync, or the target blocks
+    /// contain different edits.
+    Conflict(String),
+    /// There is no sync state for the block.
+    Unknown(String),
+    /// The block could not be pulled: ID and the error message.
+    Error(String, String),
+}
+
+/// Struct representing the result of a pull operation.
+#[derive(Debug)]
+pub struct PullResult {
+    /// The source file path.
+    pub path: PathBuf,
+    pub status: PullStatus,
+}
+
+impl SyncState {
+    /// Returns `true` when the sync state file exists in the given folder.
+    #[must_use]
+    pub fn exists(folder: &Path) -> bool {
+        folder.join(SYNC_STATE_FILE).exists()
+    }
+
+    /// Load the sync state from the given folder. Returns an empty state when
+    /// the state file not exists.
+    ///
+    /// # Errors
+    /// When could not read or parse the state file
+    pub fn load(folder: &Path) -> SyncResult<Self> {
+        let path = folder.join(SYNC_STATE_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_reader(File::open(path)?)?)
+    }
+
+    /// Save the sync state to the given folder.
+    ///
+    /// # Errors
+    /// When could not write the state file
+    pub fn save(&self, folder: &Path) -> SyncResult<()> {
+        let file = File::create(folder.join(SYNC_STATE_FILE))?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+
+    /// Returns the last synced content of the given block ID.
+    #[must_use]
+    pub fn get(&self, id: &str) -> Option<&str> {
+        self.blocks.get(id).map(String::as_str)
+    }
+
+    /// Set the synced content of the given block ID.
+    pub fn set(&mut self, id: &str, content: &str) {
+        self.blocks.insert(id.to_string(), content.to_string());
+    }
+
+    /// Record the blocks that are synced in the target files after a replace
+    /// operation. Returns `true` when the state changed.
+    pub fn record(&mut self, parse_contents: &[ContentResults], results: &[ReplaceResult]) -> bool {
+        let mut changed = false;
+        for result in results {
+            let id = match &result.status {
+                ReplaceStatus::Equal(id) | ReplaceStatus::Replaced(id, _, _, _) => id,
+                ReplaceStatus::Error(_) | ReplaceStatus::NotFound(_) => continue,
+            };
+            if let Some(content) = parse_contents.iter().find(|c| {
+                c.metadata
+                    .reference_ids(parse_contents)
+                    .contains(&id.as_str())
+            }) {
+                if self.get(&content.metadata.id) != Some(content.data.as_str()) {
+                    self.set(&content.metadata.id, &content.data);
+                    changed = true;
+                }
+            }
+        }
+        changed
+    }
+}
+
+/// Read the content of the given target markers.
+#[must_use]
+pub fn target_blocks(replace: &Replace, markers: Vec<Marker>) -> Vec<TargetBlock> {
+    markers
+        .into_iter()
+        .filter_map(|marker| {
+            let content = match std::fs::read_to_string(&marker.path) {
+                Ok(content) => content,
+                Err(err) => {
+                    tracing::error!(err = %err, path = %marker.path.display(), "could not read target file");
+                    return None;
+                }
+            };
+            match replace.find_block(&content, &marker.id) {
+                Ok(Some(content)) => Some(TargetBlock { marker, content }),
+                Ok(None) => None,
+                Err(err) => {
+                    tracing::error!(err = %err, id = marker.id, "could not find target block");
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Pull the target edits back into the source files.
+///
+/// A target block is written back only when the source block is unchanged
+/// since the last sync, and all the target blocks of the ID contain the same
+/// edit. Blocks that are equal to their target blocks are recorded as synced.
+/// When `dry_run` is `true` the results are calculated without changing the
+/// source files or the sync state.
+pub fn pull(
+    sources: &[ContentResults],
+    targets: &[TargetBlock],
+    patterns: &[Pattern],
+    state: &mut SyncState,
+    dry_run: bool,
+) -> Vec<PullResult> {
+    // the blocks of a file are written back bottom-up, so a block that
+    // changes its line count does not move the blocks that are left
+    let mut order = (0..sources.len()).collect::<Vec<_>>();
+    order.sort_by(|a, b| {
+        let (a, b) = (&sources[*a].location, &sources[*b].location);
+        a.path.cmp(&b.path).then(b.start_line.cmp(&a.start_line))
+    });
+
+    let mut results = vec![];
+    for index in order {
+        let source = &sources[index];
+        let id = source.metadata.id.as_str();
+        let path = source.location.path.clone();
+        let reference_ids = source.metadata.reference_ids(sources);
+
+        let mut edits = targets
+            .iter()
+            .filter(|target| {
+                reference_ids.contains(&target.marker.id.as_str()) && target.content != source.data
+            })
+            .map(|target| target.content.as_str())
+            .collect::<Vec<_>>();
+        edits.sort_unstable();
+        edits.dedup();
+
+        let status = match edits.as_slice() {
+            [] => {
+                if !dry_run && sources.iter().filter(|s| s.metadata.id == id).count() == 1 {
+                    state.set(id, &source.data);
+                }
+                PullStatus::Equal(id.to_string())
+            }
+            [_] if source.location.is_generated() => PullStatus::Error(
+                id.to_string(),
+                SyncError::GeneratedBlock { id: id.to_string() }.to_string(),
+            ),
+            [edit] => match state.get(id) {
+                None => PullStatus::Unknown(id.to_string()),
+                Some(synced) if synced != source.data => PullStatus::Conflict(id.to_string()),
+                Some(_) if sources.iter().filter(|s| s.metadata.id == id).count() > 1 => {
+                    PullStatus::Conflict(id.to_string())
+                }
+                Some(_) if dry_run => PullStatus::Pulled(id.to_string(), (*edit).to_string()),
+                Some(_) => match write_back(source, edit, patterns) {
+                    Ok(()) => {
+                        state.set(id, edit);
+                        PullStatus::Pulled(id.to_string(), (*edit).to_string())
+                    }
+                    Err(err) => PullStatus::Error(id.to_string(), err.to_string()),
+                },
+            },
+            _ => PullStatus::Conflict(id.to_string()),
+        };
+
+        results.push((index, PullResult { path, status }));
+    }
+
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+/// Write the given block content into the source file between the start and
+/// end patterns of the source block.
+///
+/// # Errors
+/// When could not read or write the source file, or could not reverse the
+/// pattern cleanups
+pub fn write_back(source: &ContentResults, block: &str, patterns: &[Pattern]) -> SyncResult<()> {
+    let id = source.metadata.id.as_str();
+    if source.location.is_generated() {
+        return Err(SyncError::GeneratedBlock { id: id.to_string() });
+    }
+    let content = std::fs::read_to_string(&source.location.path)?;
+    let lines = content.lines().collect::<Vec<_>>();
+
+    let (Some(start_index), Some(end_index)) = (
+        source.location.start_line.checked_sub(1),
+        source.location.end_line.checked_sub(1),
+    ) else {
+        return Err(SyncError::PatternNotFound { id: id.to_string() });
+    };
+    let (Some(start_line), Some(raw_lines)) = (
+        lines.get(start_index),
+        lines.get(start_index + 1..end_index),
+    ) else {
+        return Err(SyncError::PatternNotFound { id: id.to_string() });
+    };
+
+    let patterns = patterns
+        .iter()
+        .filter(|pattern| pattern.applies_to(&source.location.path))
+        .collect::<Vec<_>>();
+
+    let pattern = patterns
+        .iter()
+        .find(|pattern| pattern.start_with(start_line))
+        .ok_or_else(|| SyncError::PatternNotFound { id: id.to_string() })?;
+
+    if raw_lines.iter().any(|line| {
+        patterns
+            .iter()
+            .any(|p| p.start_with(line) || p.end_with(line))
+    }) {
+        return Err(SyncError::NestedBlock { id: id.to_string() });
+    }
+
+    if raw_lines
+        .iter()
+        .any(|line| patterns.iter().any(|p| p.is_directive(line)))
+    {
+        return Err(SyncError::Directive { id: id.to_string() });
+    }
+
+    // the block content was substituted, writing it back would replace the
+    // variables with their values
+    if raw_lines
+        .iter()
+        .any(|line| vars::VARIABLE_RE.is_match(line))
+    {
+        return Err(SyncError::Variable { id: id.to_string() });
+    }
+
+    let new_block = pattern
+        .reverse_cleanup(raw_lines, block)
+        .ok_or_else(|| SyncError::ReverseCleanup { id: id.to_string() })?;
+
+    let mut new_lines = lines[..=start_index]
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    new_lines.extend(new_block.lines().map(ToString::to_string));
+    new_lines.extend(lines[end_index..].iter().map(ToString::to_string));
+
+    let mut new_content = new_lines.join("\n");
+    if content.ends_with('\n') {
+        new_content.push('\n');
+    }
+    std::fs::write(&source.location.path, new_content)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use insta::assert_debug_snapshot;
+    use tree_fs::Tree;
+
+    use super::*;
+
+    fn source(path: PathBuf, id: &str, data: &str) -> ContentResults {
+        ContentResults::new_for_test(id, data, path, 2, 5)
+    }
+
+    fn target(id: &str, content: &str) -> TargetBlock {
+        TargetBlock {
+            marker: Marker {
+                id: id.to_string(),
+                path: PathBuf::from("README.md"),
+                line: 1,
+            },
+            content: content.to_string(),
+        }
+    }
+
+    #[test]
+    fn can_pull() {
+        let content = "//! # Lib\n// 📖 #START <id:intro>\n//! ## Intro\n//! Some typo\n// 📖 \
+                       #END\n";
+        let res = Tree::default().add("lib.rs", content).create().unwrap();
+        let path = res.join("lib.rs");
+
+        let sources = vec![source(path.clone(), "intro", "## Intro\n Some typo")];
+        let mut state = SyncState::default();
+        state.set("intro", "## Intro\n Some typo");
+
+        let results = pull(
+            &sources,
+            &[target("intro", "## Intro\n Some text")],
+            &[Pattern::default()],
+            &mut state,
+            false,
+        );
+
+        assert!(matches!(results[0].status, PullStatus::Pulled(_, _)));
+        assert_eq!(state.get("intro"), Some("## Intro\n Some text"));
+        assert_debug_snapshot!(std::fs::read_to_string(path).unwrap());
+    }
+
+    #[test]
+    fn pull_blocks_of_one_file() {
+        let content = "// 📖 #START <id:a>\nfirst\n// 📖 #END\n// 📖 #START <id:b>\nsecond\n// 📖 \
+                       #END\n";
+        let res = Tree::default().add("lib.rs", content).create().unwrap();
+        let path = res.join("lib.rs");
+
+        let mut sources = vec![
+            source(path.clone(), "a", "first"),
+            source(path.clone(), "b", "second"),
+        ];
+        sources[0].location.start_line = 1;
+        sources[0].location.end_line = 3;
+        sources[1].location.start_line = 4;
+        sources[1].location.end_line = 6;
+        let mut state = SyncState::default();
+        state.set("a", "first");
+        state.set("b", "second");
+
+        let results = pull(
+            &sources,
+            &[
+                target("a", "first\nwith a new line"),
+                target("b", "second edit"),
+            ],
+            &[Pattern::default()],
+            &mut state,
+            false,
+        );
+
+        assert!(matches!(&results[0].status, PullStatus::Pulled(id, _) if id == "a"));
+        assert!(matches!(&results[1].status, PullStatus::Pulled(id, _) if id == "b"));
+        assert_eq!(
+            std::fs::read_to_string(path).unwrap(),
+            "// 📖 #START <id:a>\nfirst\nwith a new line\n// 📖 #END\n// 📖 #START <id:b>\nsecond \
+             edit\n// 📖 #END\n"
+        );
+    }
+
+    #[test]
+    fn pull_with_variables() {
+        let content = "// 📖 #START <id:install>\n// cargo add rdocs@{{crate.version}}\n// 📖 \
+                       #END\n";
+        let res = Tree::default().add("lib.rs", content).create().unwrap();
+        let path = res.join("lib.rs");
+
+        let mut sources = vec![source(path.clone(), "install", "cargo add rdocs@0.1.2")];
+        sources[0].location.start_line = 1;
+        sources[0].location.end_line = 3;
+        let mut state = SyncState::default();
+        state.set("install", "cargo add rdocs@0.1.2");
+
+        let results = pull(
+            &sources,
+            &[target("install", "cargo add rdocs@0.1.3")],
+            &[Pattern::default()],
+            &mut state,
+            false,
+        );
+
+        assert!(
+            matches!(&results[0].status, PullStatus::Error(_, err) if err == "block contains variables: install")
+        );
+        assert_eq!(std::fs::read_to_string(path).unwrap(), content);
+    }
+
+    #[test]
+    fn pull_generated_block() {
+        let mut sources = vec![source(PathBuf::from("."), "version", "0.1.2")];
+        sources[0].location.start_line = 0;
+        sources[0].location.end_line = 0;
+        let mut state = SyncState::default();
+        state.set("version", "0.1.2");
+
+        let results = pull(
+            &sources,
+            &[target("version", "0.1.3")],
+            &[Pattern::default()],
+            &mut state,
+            false,
+        );
+        assert!(
+            matches!(&results[0].status, PullStatus::Error(_, err) if err == "generated block can not be pulled: version")
+        );
+    }
+
+    #[test]
+    fn pull_with_changed_source() {
+        let sources = vec![source(PathBuf::from("lib.rs"), "intro", "new source")];
+        let mut state = SyncState::default();
+
+        let results = pull(
+            &sources,
+            &[target("intro", "target edit")],
+            &[Pattern::default()],
+            &mut state,
+            true,
+        );
+        assert!(matches!(results[0].status, PullStatus::Unknown(_)));
+
+        state.set("intro", "old source");
+        let results = pull(
+            &sources,
+            &[target("intro", "target edit")],
+            &[Pattern::default()],
+            &mut state,
+            true,
+        );
+        assert!(matches!(results[0].status, PullStatus::Conflict(_)));
+    }
+}
@@ -0,0 +1,342 @@
+//! A module for rendering template files into target files.
+//!
+//! A template is a file with a `.tpl` part in its name, for example
+//! `README.tpl.md`, that is rendered into the target file without it
+//! (`README.md`). Each snippet tag in the template is replaced with the block
+//! content of the given ID, after applying the tag filters in order:
+//!
+//! ```text
+//! {{ snippet "quick-start" }}
+//! {{ snippet "quick-start" | lines(2..5) | indent(2) }}
+//! {{ snippet "quick-start" | fence }}
+//! ```
+//!
+//! # Filters
+//!
+//! - `indent(N)`: indent the non empty lines with `N` spaces, 4 by default.
+//! - `fence(LANG)`: wrap the content with a fenced code block of the given
+//!   language, the block language by default. The fence is longer than any
+//!   backtick run in the content.
+//! - `lines(A..B)`: keep the lines `A` to `B`, 1-based and inclusive. Both
+//!   bounds are optional, for example `lines(3..)`.
+use std::{
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+
+use regex::{Captures, Regex};
+
+use crate::{
+    collect::Collector,
+    errors::{TemplateError, TemplateResult},
+    out,
+    parser::ContentResults,
+    replacer::{ReplaceResult, ReplaceStatus},
+};
+
+/// The file name part that marks a template file.
+pub const TEMPLATE_MARKER: &str = "tpl";
+
+const DEFAULT_INDENT: usize = 4;
+
+/// Matches a snippet tag, for example `{{ snippet "id" | indent(2) }}`
+static SNIPPET_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"\{\{\s*snippet\s+"(?P<id>[^"]+)"\s*(?P<filters>[^}]*)\}\}"#).unwrap()
+});
+/// Matches a filter with optional arguments, for example `lines(2..5)`
+static FILTER_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(?P<name>\w+)\s*(?:\((?P<args>[^)]*)\))?$").unwrap());
+
+/// Returns the target path of the given template file, or `None` when the
+/// file is not a template.
+#[must_use]
+pub fn target_path(path: &Path) -> Option<PathBuf> {
+    let file_name = path.file_name()?.to_str()?;
+    let parts = file_name.split('.').collect::<Vec<_>>();
+    // the first part is the file stem, so `tpl.md` is not a template
+    let index = parts
+        .iter()
+        .skip(1)
+        .position(|part| *part == TEMPLATE_MARKER)?
+        + 1;
+
+    let target = parts
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i != index)
+        .map(|(_, part)| *part)
+        .collect::<Vec<_>>()
+        .join(".");
+    Some(path.with_file_name(target))
+}
+
+/// Render the snippet tags of the given template.
+///
+/// # Errors
+/// When a snippet is not found or a filter is invalid
+pub fn render(template: &str, contents: &[ContentResults]) -> TemplateResult<String> {
+    let mut error = None;
+    let rendered =
+        SNIPPET_RE.replace_all(template, |captures: &Captures<'_>| {
+            match render_snippet(&captures["id"], &captures["filters"], contents) {
+                Ok(snippet) => snippet,
+                Err(err) => {
+                    error.get_or_insert(err);
+                    String::new()
+                }
+            }
+        });
+
+    error.map_or_else(|| Ok(rendered.to_string()), Err)
+}
+
+fn render_snippet(id: &str, filters: &str, contents: &[ContentResults]) -> TemplateResult<String> {
+    let content = contents
+        .iter()
+        .find(|content| content.metadata.id == id)
+        .or_else(|| {
+            contents
+                .iter()
+                .find(|content| content.metadata.reference_ids(contents).contains(&id))
+        })
+        .ok_or_else(|| TemplateError::SnippetNotFound { id: id.to_string() })?;
+
+    let mut snippet = content.data.clone();
+    for filter in filters.split('|').map(str::trim).filter(|f| !f.is_empty()) {
+        let invalid = || TemplateError::InvalidFilter {
+            filter: filter.to_string(),
+        };
+        let captures = FILTER_RE.captures(filter).ok_or_else(invalid)?;
+        let args = captures.name("args").map(|args| args.as_str().trim());
+
+        snippet = match &captures["name"] {
+            "indent" => {
+                let width = match args {
+                    Some(args) => args.parse().map_err(|_| invalid())?,
+                    None => DEFAULT_INDENT,
+                };
+                indent(&snippet, width)
+            }
+            "fence" => {
+                let lang = args
+                    .or(content.metadata.lang.as_deref())
+                    .unwrap_or_default();
+                let fence = out::fence(&snippet);
+                format!("{fence}{lang}\n{snippet}\n{fence}")
+            }
+            "lines" => {
+                let (start, end) = args.and_then(parse_range).ok_or_else(invalid)?;
+                (1..)
+                    .zip(snippet.lines())
+                    .filter(|(number, _)| {
+                        *number >= start && !matches!(end, Some(end) if *number > end)
+                    })
+                    .map(|(_, line)| line)
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }
+            _ => return Err(invalid()),
+        };
+    }
+    Ok(snippet)
+}
+
+/// Parse a 1-based inclusive range, for example `2..5`, `2..` or `..5`.
+fn parse_range(range: &str) -> Option<(usize, Option<usize>)> {
+    let (start, end) = range.split_once("..")?;
+    let start = match start.trim() {
+        "" => 1,
+        start => start.parse().ok()?,
+    };
+    let end = match end.trim() {
+        "" => None,
+        end => Some(end.parse().ok()?),
+    };
+    Some((start, end))
+}
+
+fn indent(text: &str, width: usize) -> String {
+    let prefix = " ".repeat(width);
+    text.lines()
+        .map(|line| {
+            if line.is_empty() {
+                String::new()
+            } else {
+                format!("{prefix}{line}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Render the templates of the given collector into their target files.
+/// Returns a result per target file. When `dry_run` is `true` the results are
+/// calculated without writing the target files.
+#[must_use]
+pub fn render_templates(
+    collector: &Collector,
+    contents: &[ContentResults],
+    dry_run: bool,
+) -> Vec<ReplaceResult> {
+    collector
+        .collect_files()
+        .into_iter()
+        .filter_map(|path| {
+            let target = target_path(&path)?;
+            let span =
+                tracing::span!(tracing::Level::TRACE, "render_template", path = %path.display());
+            let _guard = span.enter();
+
+            let id = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let status = match render_template(&path, &target, contents, dry_run) {
+                Ok((rendered, None)) => {
+                    ReplaceStatus::Replaced(id, rendered.clone(), rendered, String::new())
+                }
+                Ok((rendered, Some(previous))) if rendered == previous => ReplaceStatus::Equal(id),
+                Ok((rendered, Some(previous))) => {
+                    ReplaceStatus::Replaced(id, rendered.clone(), rendered, previous)
+                }
+                Err(err) => {
+                    tracing::error!(err = %err, "could not render template");
+                    ReplaceStatus::Error(err.to_string())
+                }
+            };
+
+            Some(ReplaceResult {
+                path: target,
+                line: None,
+                status,
+            })
+        })
+        .collect()
+}
+
+/// Render the given template and write the target file when it changed.
+/// Returns the rendered content and the previous target content.
+fn render_template(
+    path: &Path,
+    target: &Path,
+    contents: &[ContentResults],
+    dry_run: bool,
+) -> TemplateResult<(String, Option<String>)> {
+    let rendered = render(&std::fs::read_to_string(path)?, contents)?;
+    let previous = if target.exists() {
+        Some(std::fs::read_to_string(target)?)
+    } else {
+        None
+    };
+
+    if !dry_run && previous.as_ref() != Some(&rendered) {
+        std::fs::write(target, &rendered)?;
+    }
+    Ok((rendered, previous))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use insta::assert_snapshot;
+    use tree_fs::Tree;
+
+    use super::*;
+
+    fn contents() -> Vec<ContentResults> {
+        let mut content = ContentResults::new_for_test(
+            "quick-start",
+            "fn main() {\n    let a = 1;\n\n    println!(\"{a}\");\n}",
+            "",
+            0,
+            0,
+        );
+        content.metadata.lang = Some("rust".to_string());
+        content.metadata.qualify("my-crate");
+        vec![content]
+    }
+
+    #[test]
+    fn can_get_target_path() {
+        assert_eq!(
+            target_path(Path::new("docs/README.tpl.md")),
+            Some(PathBuf::from("docs/README.md"))
+        );
+        assert_eq!(
+            target_path(Path::new("Makefile.tpl")),
+            Some(PathBuf::from("Makefile"))
+        );
+        assert_eq!(target_path(Path::new("tpl.md")), None);
+        assert_eq!(target_path(Path::new("README.md")), None);
+    }
+
+    #[test]
+    fn can_render() {
+        let template = r#"# Quick Start
+
+{{ snippet "quick-start" }}
+
+{{snippet "my-crate::quick-start" | lines(2..4) | indent(2)}}
+
+{{ snippet "quick-start" | lines(..1) | fence }}
+
+{{ snippet "quick-start" | lines(5..) | fence(text) }}
+"#;
+        assert_snapshot!(render(template, &contents()).unwrap());
+    }
+
+    #[test]
+    fn can_render_fence_of_fenced_content() {
+        let mut contents = contents();
+        contents[0].data = "Run:\n```sh\nrdocs collect .\n```".to_string();
+        assert_eq!(
+            render(r#"{{ snippet "quick-start" | fence(md) }}"#, &contents).unwrap(),
+            "````md\nRun:\n```sh\nrdocs collect .\n```\n````"
+        );
+    }
+
+    #[test]
+    fn render_with_errors() {
+        assert!(matches!(
+            render(r#"{{ snippet "missing" }}"#, &contents()),
+            Err(TemplateError::SnippetNotFound { .. })
+        ));
+        for filter in ["unknown", "indent(a)", "lines(2)", "lines"] {
+            assert!(matches!(
+                render(
+                    &format!(r#"{{{{ snippet "quick-start" | {filter} }}}}"#),
+                    &contents()
+                ),
+                Err(TemplateError::InvalidFilter { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn can_render_templates() {
+        let res = Tree::default()
+            .add(
+                "README.tpl.md",
+                r#"{{ snippet "quick-start" | lines(1..1) }}"#,
+            )
+            .add("other.md", "not a template")
+            .create()
+            .unwrap();
+        let collector = Collector::new(&res).unwrap();
+
+        let results = render_templates(&collector, &contents(), true);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].path, res.join("README.md"));
+        assert!(matches!(results[0].status, ReplaceStatus::Replaced(..)));
+        assert!(!res.join("README.md").exists());
+
+        let results = render_templates(&collector, &contents(), false);
+        assert!(matches!(results[0].status, ReplaceStatus::Replaced(..)));
+        assert_eq!(
+            std::fs::read_to_string(res.join("README.md")).unwrap(),
+            "fn main() {"
+        );
+        let results = render_templates(&collector, &contents(), true);
+        assert!(matches!(results[0].status, ReplaceStatus::Equal(_)));
+    }
+}
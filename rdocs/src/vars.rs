@@ -0,0 +1,225 @@
+//! A module for substituting variables in extracted content.
+//!
+//! This module provides functionality to replace `{{name}}` placeholders in
+//! the extracted content with project values, like the crate version, the
+//! latest git tag or values defined in the configuration.
+//! Substitution is enabled with the `variables` parser configuration.
+//!
+//! # Variables
+//!
+//! - `crate.name`: the package name of the nearest `Cargo.toml`.
+//! - `crate.version`: the package version of the nearest `Cargo.toml`.
+//! - `git.tag`: the latest git tag of the repository.
+//! - Any key from the parser configuration `vars` map.
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+    process::Command,
+    sync::{LazyLock, Mutex},
+};
+
+use regex::{Captures, Regex};
+
+/// Matches a variable placeholder, for example `{{ crate.version }}`
+pub static VARIABLE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\{\{\s*([\w.\-]+)\s*\}\}").unwrap());
+
+const CARGO_MANIFEST: &str = "Cargo.toml";
+
+/// Represents the package details of a crate.
+#[derive(Debug, Clone)]
+struct CrateInfo {
+    name: Option<String>,
+    version: Option<String>,
+}
+
+/// Represents the variables that can be substituted in the extracted content.
+#[derive(Debug, Default)]
+pub struct Variables {
+    vars: BTreeMap<String, String>,
+    crates: Mutex<HashMap<PathBuf, Option<CrateInfo>>>,
+    git_tags: Mutex<HashMap<PathBuf, Option<String>>>,
+}
+
+impl Variables {
+    /// Creates a new instance of [`Variables`] with the given configuration
+    /// variables.
+    #[must_use]
+    pub fn new(vars: BTreeMap<String, String>) -> Self {
+        Self {
+            vars,
+            ..Self::default()
+        }
+    }
+
+    /// Substitute the variables in the given text. `path` is the source file
+    /// of the text, used to resolve the crate and git variables. Unknown
+    /// variables are kept as is.
+    #[must_use]
+    pub fn substitute(&self, text: &str, path: &Path) -> String {
+        VARIABLE_RE
+            .replace_all(text, |captures: &Captures<'_>| {
+                let name = &captures[1];
+                self.resolve(name, path).unwrap_or_else(|| {
+                    tracing::debug!(name, "variable not found");
+                    captures[0].to_string()
+                })
+            })
+            .to_string()
+    }
+
+    /// Resolve the value of the given variable name.
+    fn resolve(&self, name: &str, path: &Path) -> Option<String> {
+        if let Some(value) = self.vars.get(name) {
+            return Some(value.clone());
+        }
+
+        let dir = path.parent()?;
+        match name {
+            "crate.name" => self.crate_info(dir)?.name,
+            "crate.version" => self.crate_info(dir)?.version,
+            "git.tag" => self.git_tag(dir),
+            _ => None,
+        }
+    }
+
+    /// Returns the package details of the nearest `Cargo.toml` of the given
+    /// folder.
+    fn crate_info(&self, dir: &Path) -> Option<CrateInfo> {
+        let mut crates = self.crates.lock().ok()?;
+        crates
+            .entry(dir.to_path_buf())
+            .or_insert_with(|| read_crate_info(&find_manifest(dir)?))
+            .clone()
+    }
+
+    /// Returns the latest git tag of the repository of the given folder.
+    fn git_tag(&self, dir: &Path) -> Option<String> {
+        let mut git_tags = self.git_tags.lock().ok()?;
+        git_tags
+            .entry(dir.to_path_buf())
+            .or_insert_with(|| {
+                let output = Command::new("git")
+                    .args(["describe", "--tags", "--abbrev=0"])
+                    .current_dir(dir)
+                    .output()
+                    .ok()?;
+                if !output.status.success() {
+                    tracing::debug!("git tag not found");
+                    return None;
+                }
+                Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+            })
+            .clone()
+    }
+}
+
+/// Returns the nearest `Cargo.toml` of the given folder.
+pub(crate) fn find_manifest(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|ancestor| ancestor.join(CARGO_MANIFEST))
+        .find(|path| path.is_file())
+}
+
+/// Returns the package name of the given manifest.
+pub(crate) fn package_name(manifest_path: &Path) -> Option<String> {
+    read_crate_info(manifest_path)?.name
+}
+
+/// Read the package details from the given manifest. Values that are inherited
+/// from the workspace are read from the workspace manifest.
+fn read_crate_info(manifest_path: &Path) -> Option<CrateInfo> {
+    let manifest = read_manifest(manifest_path)?;
+    let package = manifest.get("package")?;
+
+    let field = |name: &str| -> Option<String> {
+        match package.get(name)? {
+            toml::Value::String(value) => Some(value.clone()),
+            toml::Value::Table(table) if table.get("workspace")?.as_bool()? => {
+                workspace_package_field(manifest_path, name)
+            }
+            _ => None,
+        }
+    };
+
+    Some(CrateInfo {
+        name: field("name"),
+        version: field("version"),
+    })
+}
+
+/// Read a package field from the `[workspace.package]` table of the nearest
+/// workspace manifest.
+fn workspace_package_field(manifest_path: &Path, name: &str) -> Option<String> {
+    manifest_path
+        .parent()?
+        .ancestors()
+        .map(|ancestor| ancestor.join(CARGO_MANIFEST))
+        .filter(|path| path.is_file())
+        .filter_map(|path| read_manifest(&path))
+        .find_map(|manifest| {
+            manifest
+                .get("workspace")?
+                .get("package")?
+                .get(name)?
+                .as_str()
+                .map(ToString::to_string)
+        })
+}
+
+fn read_manifest(path: &Path) -> Option<toml::Value> {
+    let content = std::fs::read_to_string(path).ok()?;
+    match toml::from_str(&content) {
+        Ok(manifest) => Some(manifest),
+        Err(err) => {
+            tracing::debug!(err = %err, path = %path.display(), "could not parse manifest");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use tree_fs::Tree;
+
+    use super::*;
+
+    #[test]
+    fn can_substitute() {
+        let res = Tree::default()
+            .add(
+                "Cargo.toml",
+                "[workspace]\nmembers = [\"app\"]\n\n[workspace.package]\nversion = \"1.2.3\"\n",
+            )
+            .add(
+                "app/Cargo.toml",
+                "[package]\nname = \"app\"\nversion.workspace = true\n",
+            )
+            .add("app/src/lib.rs", "")
+            .create()
+            .unwrap();
+
+        let vars = Variables::new(BTreeMap::from([(
+            "registry".to_string(),
+            "crates.io".to_string(),
+        )]));
+
+        assert_eq!(
+            vars.substitute(
+                "cargo add {{crate.name}}@{{ crate.version }} from {{registry}} {{unknown}}",
+                &res.join("app/src/lib.rs")
+            ),
+            "cargo add app@1.2.3 from crates.io {{unknown}}"
+        );
+    }
+
+    #[test]
+    fn keep_format_braces() {
+        let vars = Variables::default();
+        assert_eq!(
+            vars.substitute(r#"format!("{{}}", a)"#, Path::new("lib.rs")),
+            r#"format!("{{}}", a)"#
+        );
+    }
+}
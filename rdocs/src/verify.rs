@@ -0,0 +1,324 @@
+//! A module for verifying that extracted blocks compile before they are
+//! injected into the documentation.
+//!
+//! The block language is taken from the `<lang:[LANG]>` tag of the start
+//! pattern line, or from the source file extension when configured.
+//!
+//! # Languages
+//!
+//! - `rust`: the block is written into a scratch crate that depends on the
+//!   crate of the source file, and checked with `cargo check`. Blocks outside
+//!   of a crate are checked with `rustc`. Blocks without a `main` function are
+//!   wrapped with one, like doctests.
+//! - `sh`: the block is checked with `sh -n`.
+use std::{
+    collections::BTreeMap,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::{VerifyError, VerifyResult},
+    parser::ContentResults,
+    vars,
+};
+
+const SCRATCH_CRATE_NAME: &str = "rdocs-verify";
+
+/// Enum representing the languages that can be verified.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Lang {
+    #[serde(alias = "rs")]
+    Rust,
+    #[serde(alias = "bash", alias = "shell")]
+    Sh,
+}
+
+/// Represents the verify configuration.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Config {
+    /// The language of blocks without a `<lang:[LANG]>` tag, by the source
+    /// file extension. For example `sh: sh`.
+    #[serde(default)]
+    pub extensions: BTreeMap<String, Lang>,
+    /// The Rust edition of the verified blocks.
+    #[serde(default = "default_edition")]
+    pub edition: String,
+}
+
+fn default_edition() -> String {
+    "2021".to_string()
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            extensions: BTreeMap::new(),
+            edition: default_edition(),
+        }
+    }
+}
+
+/// Represents a block that failed the verification.
+#[derive(Debug)]
+pub struct Failure {
+    pub id: String,
+    /// The source file path.
+    pub path: PathBuf,
+    /// The start pattern line of the block.
+    pub line: usize,
+    pub lang: Lang,
+    /// The compiler output.
+    pub message: String,
+}
+
+/// Represents a verifier of extracted blocks.
+#[derive(Debug, Default)]
+pub struct Verifier {
+    config: Config,
+}
+
+impl Lang {
+    /// Returns the language of the given name.
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "rust" | "rs" => Some(Self::Rust),
+            "sh" | "bash" | "shell" => Some(Self::Sh),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Lang {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Rust => write!(f, "rust"),
+            Self::Sh => write!(f, "sh"),
+        }
+    }
+}
+
+impl Verifier {
+    /// Creates a new instance of [`Verifier`] with the specified
+    /// configuration.
+    #[must_use]
+    pub const fn new(config: Config) -> Self {
+        Self { config }
+    }
+
+    /// Returns the language of the given block. Blocks with an unknown
+    /// language are not verified.
+    #[must_use]
+    pub fn lang(&self, content: &ContentResults) -> Option<Lang> {
+        if let Some(lang) = &content.metadata.lang {
+            return Lang::from_name(lang);
+        }
+        let extension = content.location.path.extension()?.to_str()?;
+        self.config.extensions.get(extension).copied()
+    }
+
+    /// Verify the given blocks and returns the blocks that failed.
+    ///
+    /// # Errors
+    /// When could not prepare the scratch folder or run the verify command
+    pub fn verify(&self, contents: &[ContentResults]) -> VerifyResult<Vec<Failure>> {
+        let scratch = std::env::temp_dir().join(format!("rdocs-verify-{}", std::process::id()));
+        let result = self.verify_in(contents, &scratch);
+        if scratch.exists() {
+            if let Err(err) = std::fs::remove_dir_all(&scratch) {
+                tracing::debug!(err = %err, "could not remove scratch folder");
+            }
+        }
+        result
+    }
+
+    fn verify_in(&self, contents: &[ContentResults], scratch: &Path) -> VerifyResult<Vec<Failure>> {
+        let mut failures = vec![];
+        for content in contents {
+            let Some(lang) = self.lang(content) else {
+                continue;
+            };
+
+            let span =
+                tracing::span!(tracing::Level::TRACE, "verify", id = content.metadata.id, %lang);
+            let _guard = span.enter();
+
+            let message = match lang {
+                Lang::Rust => self.verify_rust(content, scratch)?,
+                Lang::Sh => verify_sh(&content.data)?,
+            };
+
+            if let Some(message) = message {
+                failures.push(Failure {
+                    id: content.metadata.id.clone(),
+                    path: content.location.path.clone(),
+                    line: content.location.start_line,
+                    lang,
+                    message,
+                });
+            }
+        }
+        Ok(failures)
+    }
+
+    /// Verify a Rust block. Returns the compiler output when the block does
+    /// not compile.
+    fn verify_rust(
+        &self,
+        content: &ContentResults,
+        scratch: &Path,
+    ) -> VerifyResult<Option<String>> {
+        let source = rust_source(&content.data);
+
+        let dependency = vars::find_manifest(&content.location.path).and_then(|manifest| {
+            let name = vars::package_name(&manifest)?;
+            Some((name, manifest.parent()?.to_path_buf()))
+        });
+
+        let Some((name, crate_dir)) = dependency else {
+            tracing::trace!("crate not found, verify with rustc");
+            std::fs::create_dir_all(scratch)?;
+            let mut command = Command::new("rustc");
+            command.args([
+                "--edition",
+                &self.config.edition,
+                "--crate-type",
+                "bin",
+                "--crate-name",
+                "rdocs_verify",
+                "--emit=metadata",
+                "--error-format=short",
+                "--out-dir",
+            ]);
+            command.arg(scratch).arg("-");
+            return run(command, &source);
+        };
+
+        let crate_path = scratch.join(&name);
+        std::fs::create_dir_all(crate_path.join("src"))?;
+        std::fs::write(
+            crate_path.join("Cargo.toml"),
+            format!(
+                "[package]\nname = \"{SCRATCH_CRATE_NAME}\"\nversion = \"0.0.0\"\nedition = \
+                 \"{}\"\npublish = false\n\n[workspace]\n\n[dependencies]\n{name} = {{ path = \
+                 {:?} }}\n",
+                self.config.edition,
+                crate_dir.display().to_string()
+            ),
+        )?;
+        std::fs::write(crate_path.join("src/main.rs"), source)?;
+
+        let mut command = Command::new("cargo");
+        command
+            .args(["check", "--quiet", "--message-format", "short"])
+            .current_dir(&crate_path)
+            // share the build artifacts of the dependency between runs
+            .env(
+                "CARGO_TARGET_DIR",
+                std::env::temp_dir().join("rdocs-verify-target"),
+            );
+        run(command, "")
+    }
+}
+
+/// Verify a shell block. Returns the shell output when the block has syntax
+/// errors.
+fn verify_sh(data: &str) -> VerifyResult<Option<String>> {
+    let mut command = Command::new("sh");
+    command.arg("-n");
+    run(command, data)
+}
+
+/// Wrap the given Rust block with a `main` function when the block does not
+/// have one.
+fn rust_source(data: &str) -> String {
+    if data.contains("fn main") {
+        format!("#![allow(unused)]\n{data}\n")
+    } else {
+        format!("#![allow(unused)]\nfn main() {{\n{data}\n}}\n")
+    }
+}
+
+/// Run the given command with the given stdin. Returns the command output
+/// when the command fails.
+fn run(mut command: Command, stdin: &str) -> VerifyResult<Option<String>> {
+    let program = command.get_program().to_string_lossy().to_string();
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| VerifyError::Command {
+            program: program.clone(),
+            err,
+        })?;
+
+    if let Some(mut input) = child.stdin.take() {
+        input.write_all(stdin.as_bytes())?;
+    }
+
+    let output = child.wait_with_output()?;
+    if output.status.success() {
+        return Ok(None);
+    }
+
+    let mut message = String::from_utf8_lossy(&output.stderr).to_string();
+    message.push_str(&String::from_utf8_lossy(&output.stdout));
+    Ok(Some(message.trim().to_string()))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+
+    use tree_fs::Tree;
+
+    use super::*;
+
+    fn content(path: PathBuf, id: &str, lang: Option<&str>, data: &str) -> ContentResults {
+        let mut content = ContentResults::new_for_test(id, data, path, 1, 3);
+        content.metadata.lang = lang.map(ToString::to_string);
+        content
+    }
+
+    #[test]
+    fn can_verify() {
+        let res = Tree::default().add("script.sh", "").create().unwrap();
+        let path = res.join("script.sh");
+
+        let verifier = Verifier::new(Config {
+            extensions: BTreeMap::from([("sh".to_string(), Lang::Sh)]),
+            ..Config::default()
+        });
+
+        let contents = vec![
+            content(path.clone(), "rust-ok", Some("rust"), "let a = 1 + 2;"),
+            content(
+                path.clone(),
+                "rust-main",
+                Some("rs"),
+                "fn main() {\n    println!(\"hi\");\n}",
+            ),
+            content(
+                path.clone(),
+                "rust-error",
+                Some("rust"),
+                "let a: i32 = \"a\";",
+            ),
+            content(path.clone(), "sh-ok", None, "echo hello"),
+            content(path.clone(), "sh-error", Some("bash"), "if true; then"),
+            content(path, "text", Some("text"), "not verified"),
+        ];
+
+        let failures = verifier.verify(&contents).unwrap();
+        assert_eq!(
+            failures.iter().map(|f| f.id.as_str()).collect::<Vec<_>>(),
+            vec!["rust-error", "sh-error"]
+        );
+        assert!(failures[0].message.contains("mismatched types"));
+    }
+}
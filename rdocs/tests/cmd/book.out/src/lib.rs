@@ -0,0 +1,9 @@
+// 📖 #START <id:greet> <lang:rust>
+pub fn greet(name: &str) -> String {
+    format!("Hello, {name}!")
+}
+// 📖 #END
+
+// 📖 #START <id:install> <lang:sh>
+cargo add greet
+// 📖 #END
@@ -0,0 +1,9 @@
+// 📖 #START <id:greet> <lang:rust>
+pub fn greet(name: &str) -> String {
+    format!("Hello, {name}!")
+}
+// 📖 #END
+
+// 📖 #START <id:scripts/install> <lang:bash>
+cargo add greet
+// 📖 #END
@@ -0,0 +1 @@
+cargo add greet
\ No newline at end of file
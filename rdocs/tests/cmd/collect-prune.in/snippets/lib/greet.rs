@@ -0,0 +1 @@
+pub fn greet
\ No newline at end of file
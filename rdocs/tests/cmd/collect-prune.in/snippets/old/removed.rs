@@ -0,0 +1 @@
+old content
\ No newline at end of file
@@ -0,0 +1,5 @@
+// 📖 #START <id:greet> <lang:rust>
+pub fn greet(name: &str) -> String {
+    format!("Hello, {name}!")
+}
+// 📖 #END
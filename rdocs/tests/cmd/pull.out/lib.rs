@@ -0,0 +1,11 @@
+//! # Lib
+// 📖 #START <id:introduction>
+//! ## Introduction
+//! Rdocs keeps the documentation aligned with the code.
+// 📖 #END
+
+// 📖 #START <id:example>
+fn main() {
+    println!("example");
+}
+// 📖 #END
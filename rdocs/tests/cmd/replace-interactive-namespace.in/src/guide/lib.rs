@@ -0,0 +1,3 @@
+// 📖 #START <id:qs>
+let guide = rdocs::Guide::new();
+// 📖 #END
@@ -0,0 +1,6 @@
+/// Function to add two numbers and return the result
+//📖 #START <id:adding_numbers> <lang:rust>
+fn add_numbers(a: i32, b: i32) -> i32 {
+    a + b
+}
+//📖 #END